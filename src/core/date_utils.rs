@@ -1,70 +1,307 @@
 use std::fs;
-use std::path::Path;
-use chrono::{DateTime, NaiveDateTime, Utc, TimeZone};
-use exif::{In, Reader, Tag, Exif};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Timelike, Utc, TimeZone};
+use exif::{In, Reader, Tag, Exif, Value};
 use anyhow::Result;
 use log::warn;
 
-fn parse_exif_datetime(s: &str) -> Option<DateTime<Utc>> {
-    if let Ok(naive) = NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S") {
-        return Some(Utc.from_utc_datetime(&naive));
+use crate::core::{filename_date, isobmff, matroska, mpegts, png, raw, riff, sidecar, xmp};
+use crate::core::media_format::MediaFormat;
+use crate::core::types::DateSource;
+
+#[derive(Debug, Clone)]
+pub struct ExtractedDate {
+    pub date_taken: DateTime<Utc>,
+    pub utc_offset: Option<FixedOffset>,
+    pub source: DateSource,
+    // Picked up from the same EXIF block as the date; used by folder layouts.
+    pub camera_model: Option<String>,
+}
+
+impl ExtractedDate {
+    fn new(date_taken: DateTime<Utc>, source: DateSource) -> Self {
+        Self { date_taken, utc_offset: None, source, camera_model: None }
+    }
+
+    fn with_offset(date_taken: DateTime<Utc>, utc_offset: Option<FixedOffset>, source: DateSource) -> Self {
+        Self { date_taken, utc_offset, source, camera_model: None }
+    }
+}
+
+fn parse_exif_datetime(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S").ok()
+}
+
+// SubSecTime holds the fractional digits only, e.g. "45" is 0.45 s.
+fn parse_subsec(s: &str) -> Option<u32> {
+    let digits: String = s.chars().take_while(|c| c.is_ascii_digit()).take(9).collect();
+    if digits.is_empty() {
+        return None;
+    }
+    format!("{:0<9}", digits).parse().ok()
+}
+
+// AVI and PNG writers use anything from ctime-style strings to bare dates.
+fn parse_free_form_datetime(s: &str) -> Option<DateTime<Utc>> {
+    const DATETIME_FORMATS: &[&str] = &[
+        "%a %b %d %H:%M:%S %Y",
+        "%Y:%m:%d %H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ];
+    const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y:%m:%d", "%Y/%m/%d"];
+
+    let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+    for fmt in DATETIME_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(&s, fmt) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+    for fmt in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(&s, fmt) {
+            return Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?));
+        }
     }
     None
 }
 
+// `display_value` reformats dates as `YYYY-MM-DD`, so read the raw ASCII instead.
+// CR3 stores the Exif IFD as a standalone TIFF block, so its tags are also
+// looked up by number outside their usual IFD context.
+fn exif_ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY).or_else(|| {
+        exif.fields().find(|f| f.tag.number() == tag.number() && f.ifd_num == In::PRIMARY)
+    })?;
+    match &field.value {
+        Value::Ascii(values) => {
+            let s = String::from_utf8_lossy(values.first()?);
+            let s = s.trim_end_matches('\0').trim();
+            if s.is_empty() { None } else { Some(s.to_string()) }
+        },
+        _ => None,
+    }
+}
+
+// EXIF date strings are local wall-clock time. The matching OffsetTime* tag
+// (EXIF 2.31+) turns them into a real instant; without it they are kept as-is.
+fn exif_date_field(exif: &Exif, date_tag: Tag, offset_tag: Tag, subsec_tag: Tag, source: DateSource) -> Option<ExtractedDate> {
+    let mut naive = parse_exif_datetime(&exif_ascii_field(exif, date_tag)?)?;
+    if let Some(nanos) = exif_ascii_field(exif, subsec_tag).and_then(|s| parse_subsec(&s)) {
+        naive = naive.with_nanosecond(nanos).unwrap_or(naive);
+    }
+
+    let utc_offset = exif_ascii_field(exif, offset_tag)
+        .or_else(|| exif_ascii_field(exif, Tag::OffsetTime))
+        .and_then(|s| s.parse::<FixedOffset>().ok());
+
+    let date_taken = match utc_offset {
+        Some(offset) => offset.from_local_datetime(&naive).single()?.with_timezone(&Utc),
+        None => Utc.from_utc_datetime(&naive),
+    };
+
+    Some(ExtractedDate::with_offset(date_taken, utc_offset, source))
+}
+
+fn date_from_exif(exif: &Exif) -> Option<ExtractedDate> {
+    // Priority 1: DateTimeOriginal
+    let date = exif_date_field(exif, Tag::DateTimeOriginal, Tag::OffsetTimeOriginal, Tag::SubSecTimeOriginal, DateSource::ExifOriginal)
+        // Priority 2: DateTime
+        .or_else(|| exif_date_field(exif, Tag::DateTime, Tag::OffsetTime, Tag::SubSecTime, DateSource::ExifDateTime))
+        // Priority 3: DateTimeDigitized
+        .or_else(|| exif_date_field(exif, Tag::DateTimeDigitized, Tag::OffsetTimeDigitized, Tag::SubSecTimeDigitized, DateSource::ExifDigitized))?;
+    Some(ExtractedDate { camera_model: exif_ascii_field(exif, Tag::Model), ..date })
+}
+
+fn read_sidecar_date(path: &Path) -> Result<Option<ExtractedDate>> {
+    if sidecar::is_xmp(path) {
+        let date = xmp::read_sidecar_date(path)?;
+        return Ok(date.map(|(date_taken, utc_offset)| ExtractedDate::with_offset(date_taken, utc_offset, DateSource::Sidecar)));
+    }
+    let date = sidecar::read_takeout_date(path)?;
+    Ok(date.map(|dt| ExtractedDate::new(dt, DateSource::Sidecar)))
+}
+
+fn date_from_sidecars(sidecars: &[PathBuf]) -> Option<ExtractedDate> {
+    sidecars.iter().find_map(|path| match read_sidecar_date(path) {
+        Ok(date) => date,
+        Err(e) => {
+            warn!("Failed to read sidecar {:?}: {}", path, e);
+            None
+        }
+    })
+}
+
+// Names carry local wall-clock time, kept naive like offset-less EXIF dates.
+fn date_from_filename(path: &Path) -> Option<ExtractedDate> {
+    let naive = filename_date::parse_filename_date(path)?;
+    Some(ExtractedDate::new(Utc.from_utc_datetime(&naive), DateSource::Filename))
+}
+
 pub fn get_file_modification_date(path: &Path) -> Result<DateTime<Utc>> {
     let metadata = fs::metadata(path)?;
     let modified = metadata.modified()?;
     Ok(DateTime::from(modified))
 }
 
-pub fn extract_photo_date(path: &Path) -> Result<(DateTime<Utc>, bool)> {
-    let file_res = fs::File::open(path);
-    if let Ok(file) = file_res {
-        let mut bufreader = std::io::BufReader::new(&file);
-        let reader = Reader::new();
-        
-        if let Ok(exif) = reader.read_from_container(&mut bufreader) {
-            let exif: Exif = exif;
-            // Priority 1: DateTimeOriginal
-            if let Some(field) = exif.get_field(Tag::DateTimeOriginal, In::PRIMARY) {
-                let s = field.display_value().to_string();
-                if !s.is_empty() {
-                     if let Some(dt) = parse_exif_datetime(&s) {
-                        return Ok((dt, false));
-                    }
-                }
-            }
+fn read_exif_date(path: &Path) -> Option<ExtractedDate> {
+    let file = fs::File::open(path).ok()?;
+    let mut bufreader = std::io::BufReader::new(&file);
+    let exif = Reader::new().read_from_container(&mut bufreader).ok()?;
+    date_from_exif(&exif)
+}
 
-            // Priority 2: DateTime
-             if let Some(field) = exif.get_field(Tag::DateTime, In::PRIMARY) {
-                let s = field.display_value().to_string();
-                if !s.is_empty() {
-                     if let Some(dt) = parse_exif_datetime(&s) {
-                        return Ok((dt, true)); 
-                    }
-                }
-            }
-            
-            // Priority 3: DateTimeDigitized
-             if let Some(field) = exif.get_field(Tag::DateTimeDigitized, In::PRIMARY) {
-                let s = field.display_value().to_string();
-                if !s.is_empty() {
-                     if let Some(dt) = parse_exif_datetime(&s) {
-                        return Ok((dt, true)); 
-                    }
-                }
-            }
+// PNG "Creation Time" is meant to be RFC 1123 but is often EXIF or ISO style.
+fn parse_png_creation_time(s: &str) -> Option<ExtractedDate> {
+    let parsed = DateTime::parse_from_rfc2822(s).or_else(|_| DateTime::parse_from_rfc3339(s));
+    if let Ok(dt) = parsed {
+        return Some(ExtractedDate::with_offset(dt.with_timezone(&Utc), Some(*dt.offset()), DateSource::ContainerMetadata));
+    }
+    parse_free_form_datetime(s).map(|dt| ExtractedDate::new(dt, DateSource::ContainerMetadata))
+}
+
+fn read_raw_date(path: &Path) -> Option<ExtractedDate> {
+    match raw::read_raw_exif(path) {
+        // CR3 keeps the camera model (IFD0) and the dates (Exif IFD) in separate blocks.
+        Ok(blocks) => blocks.iter().find_map(date_from_exif).map(|date| ExtractedDate {
+            camera_model: date.camera_model.or_else(|| blocks.iter().find_map(|exif| exif_ascii_field(exif, Tag::Model))),
+            ..date
+        }),
+        Err(e) => {
+            warn!("Failed to read RAW metadata for {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+// The generic reader handles WebP but not EXIF chunks that keep the JPEG
+// "Exif\0\0" prefix.
+fn read_webp_date(path: &Path) -> Option<ExtractedDate> {
+    match riff::read_webp_exif(path) {
+        Ok(raw) => raw
+            .and_then(|raw| Reader::new().read_raw(raw).ok())
+            .and_then(|exif| date_from_exif(&exif)),
+        Err(e) => {
+            warn!("Failed to read WebP chunks for {:?}: {}", path, e);
+            None
         }
     }
-    
+}
+
+pub fn extract_photo_date(path: &Path, sidecars: &[PathBuf]) -> Result<ExtractedDate> {
+    // RAW/TIFF files are read from a header prefix before falling back to the
+    // generic reader, which loads TIFF files in full. HEIC and AVIF EXIF items
+    // are found by the generic reader.
+    if let Some(date) = read_raw_date(path).or_else(|| read_exif_date(path)).or_else(|| read_webp_date(path)) {
+        return Ok(date);
+    }
+
+    let png_meta = match png::read_png_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            warn!("Failed to read PNG chunks for {:?}: {}", path, e);
+            None
+        }
+    };
+
+    if let Some(meta) = &png_meta {
+        let exif_date = meta.exif.clone()
+            .and_then(|raw| Reader::new().read_raw(raw).ok())
+            .and_then(|exif| date_from_exif(&exif));
+        if let Some(date) = exif_date {
+            return Ok(date);
+        }
+        if let Some(date) = meta.creation_time.as_deref().and_then(parse_png_creation_time) {
+            return Ok(date);
+        }
+    }
+
+    // PNG keeps its packet in a text chunk, wherever that sits in the file.
+    let embedded_xmp = match png_meta.as_ref().and_then(|meta| meta.xmp.as_deref()) {
+        Some(packet) => Ok(xmp::read_packet_date(packet)),
+        None => xmp::read_embedded_date(path),
+    };
+    match embedded_xmp {
+        Ok(Some((date_taken, utc_offset))) => {
+            return Ok(ExtractedDate::with_offset(date_taken, utc_offset, DateSource::Xmp));
+        },
+        Ok(None) => {},
+        Err(e) => warn!("Failed to read embedded XMP for {:?}: {}", path, e),
+    }
+
+    if let Some(date) = date_from_sidecars(sidecars) {
+        return Ok(date);
+    }
+
+    if let Some(date) = date_from_filename(path) {
+        return Ok(date);
+    }
+
+    // tIME is the last time the image was modified, so it only beats the
+    // file system timestamp.
+    if let Some(naive) = png_meta.and_then(|meta| meta.modification_time) {
+        return Ok(ExtractedDate::new(Utc.from_utc_datetime(&naive), DateSource::ContainerModified));
+    }
+
     let mod_date = get_file_modification_date(path)?;
     warn!("No EXIF data found for {:?}, using file modification date: {}", path, mod_date);
-    Ok((mod_date, true))
+    Ok(ExtractedDate::new(mod_date, DateSource::FileMtime))
+}
+
+fn extract_avi_date(path: &Path) -> Result<Option<ExtractedDate>> {
+    let meta = match riff::read_avi_metadata(path)? {
+        Some(meta) => meta,
+        None => return Ok(None),
+    };
+
+    // IDIT is the AVI equivalent of DateTimeOriginal.
+    if let Some(dt) = meta.date_time_original.as_deref().and_then(parse_free_form_datetime) {
+        return Ok(Some(ExtractedDate::new(dt, DateSource::ContainerMetadata)));
+    }
+
+    if let Some(raw) = meta.exif {
+        if let Ok(exif) = Reader::new().read_raw(raw) {
+            if let Some(date) = date_from_exif(&exif) {
+                return Ok(Some(date));
+            }
+        }
+    }
+
+    Ok(meta.creation_date.as_deref().and_then(parse_free_form_datetime).map(|dt| ExtractedDate::new(dt, DateSource::ContainerMetadata)))
 }
 
-pub fn extract_video_date(path: &Path) -> Result<(DateTime<Utc>, bool)> {
+// `format` is the one the scanner sniffed, so mislabelled files still reach
+// the right parser and each file is only read by one.
+fn extract_container_date(path: &Path, format: MediaFormat) -> Result<Option<ExtractedDate>> {
+    match format {
+        MediaFormat::Avi => extract_avi_date(path),
+        MediaFormat::Mkv => Ok(matroska::read_date_utc(path)?.map(|dt| ExtractedDate::new(dt, DateSource::ContainerMetadata))),
+        // AVCHD times are local wall-clock time, kept naive like offset-less EXIF dates.
+        MediaFormat::Mts => Ok(mpegts::read_recording_date(path)?.map(|naive| {
+            ExtractedDate::new(Utc.from_utc_datetime(&naive), DateSource::ContainerMetadata)
+        })),
+        _ => Ok(isobmff::read_creation_date(path)?.map(|dt| {
+            ExtractedDate::with_offset(dt.with_timezone(&Utc), Some(*dt.offset()), DateSource::ContainerMetadata)
+        })),
+    }
+}
+
+pub fn extract_video_date(path: &Path, format: MediaFormat, sidecars: &[PathBuf]) -> Result<ExtractedDate> {
+    match extract_container_date(path, format) {
+        Ok(Some(date)) => return Ok(date),
+        Ok(None) => {},
+        Err(e) => warn!("Failed to read container metadata for {:?}: {}", path, e),
+    }
+
+    if let Some(date) = date_from_sidecars(sidecars) {
+        return Ok(date);
+    }
+
+    if let Some(date) = date_from_filename(path) {
+        return Ok(date);
+    }
+
     let mod_date = get_file_modification_date(path)?;
-    warn!("Using file modification date for video (MVP): {:?} -> {}", path, mod_date);
-    Ok((mod_date, true))
+    warn!("No container creation date found for {:?}, using file modification date: {}", path, mod_date);
+    Ok(ExtractedDate::new(mod_date, DateSource::FileMtime))
 }
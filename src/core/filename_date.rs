@@ -0,0 +1,86 @@
+use std::path::Path;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+// Phones and messengers only started embedding dates in names in the digital
+// era; anything outside this range is a counter or an ID, not a date.
+const MIN_YEAR: i32 = 1990;
+const MAX_YEAR: i32 = 2099;
+
+// Splits a file stem into its runs of ASCII digits, e.g.
+// `IMG_20230714_153012` -> ["20230714", "153012"].
+fn digit_groups(stem: &str) -> Vec<&str> {
+    stem.split(|c: char| !c.is_ascii_digit())
+        .filter(|g| !g.is_empty())
+        .collect()
+}
+
+fn make_date(year: &str, month: &str, day: &str) -> Option<NaiveDate> {
+    let year: i32 = year.parse().ok()?;
+    if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)
+}
+
+fn make_time(hour: &str, minute: &str, second: &str) -> Option<NaiveTime> {
+    NaiveTime::from_hms_opt(hour.parse().ok()?, minute.parse().ok()?, second.parse().ok()?)
+}
+
+// `153012` or `153012345` (Pixel appends milliseconds).
+fn compact_time(group: &str) -> Option<NaiveTime> {
+    if group.len() < 6 || group.len() > 9 {
+        return None;
+    }
+    make_time(&group[0..2], &group[2..4], &group[4..6])
+}
+
+// Time following a date: either one compact group or three 2-digit groups
+// (`15-30-12`, `15.30.12`).
+fn time_after(groups: &[&str]) -> Option<NaiveTime> {
+    if let Some(time) = groups.first().and_then(|g| compact_time(g)) {
+        return Some(time);
+    }
+    match groups {
+        [h, m, s, ..] if h.len() == 2 && m.len() == 2 && s.len() == 2 => make_time(h, m, s),
+        _ => None,
+    }
+}
+
+/// Infers a capture date from common camera, phone and messenger naming
+/// schemes such as `IMG_20230714_153012`, `IMG-20230714-WA0003`,
+/// `PXL_20230714_153012345` or `Screenshot_2023-07-14-15-30-12`.
+///
+/// The result is local wall-clock time. Names that only carry a date
+/// (WhatsApp) resolve to midnight.
+pub fn parse_filename_date(path: &Path) -> Option<NaiveDateTime> {
+    let stem = path.file_stem()?.to_str()?;
+    let groups = digit_groups(stem);
+
+    for (i, &group) in groups.iter().enumerate() {
+        let (date, rest) = match group.len() {
+            // 20230714153012
+            14 => {
+                let date = make_date(&group[0..4], &group[4..6], &group[6..8]);
+                let time = make_time(&group[8..10], &group[10..12], &group[12..14]);
+                if let (Some(date), Some(time)) = (date, time) {
+                    return Some(date.and_time(time));
+                }
+                continue;
+            },
+            // 20230714
+            8 => (make_date(&group[0..4], &group[4..6], &group[6..8]), &groups[i + 1..]),
+            // 2023-07-14
+            4 if groups.len() >= i + 3 && groups[i + 1].len() == 2 && groups[i + 2].len() == 2 => {
+                (make_date(group, groups[i + 1], groups[i + 2]), &groups[i + 3..])
+            },
+            _ => continue,
+        };
+
+        if let Some(date) = date {
+            let time = time_after(rest).unwrap_or(NaiveTime::MIN);
+            return Some(date.and_time(time));
+        }
+    }
+
+    None
+}
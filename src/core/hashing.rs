@@ -0,0 +1,96 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use anyhow::Result;
+use sha2::{Sha256, Digest};
+
+// Bytes hashed from each end of a file for the partial hash.
+const EDGE_BLOCK: u64 = 64 * 1024;
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Content fingerprints of a file, from cheapest to most expensive. Each stage
+/// is computed on first use and cached, so comparing many files reads each one
+/// in full at most once, and only when the cheaper stages match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileHash {
+    pub size: Option<u64>,
+    // SHA-256 of the first and last 64 KiB; the full hash for smaller files.
+    pub partial: Option<String>,
+    // SHA-256 of the whole file.
+    pub full: Option<String>,
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    loop {
+        let count = file.read(&mut buffer)?;
+        if count == 0 { break; }
+        hasher.update(&buffer[..count]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn sha256_edges(path: &Path, size: u64) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut block = vec![0; EDGE_BLOCK as usize];
+
+    file.read_exact(&mut block)?;
+    hasher.update(&block);
+    file.seek(SeekFrom::Start(size - EDGE_BLOCK))?;
+    file.read_exact(&mut block)?;
+    hasher.update(&block);
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+impl FileHash {
+    pub fn with_size(size: u64) -> Self {
+        Self { size: Some(size), ..Self::default() }
+    }
+
+    pub fn size(&mut self, path: &Path) -> Result<u64> {
+        if let Some(size) = self.size {
+            return Ok(size);
+        }
+        let size = fs::metadata(path)?.len();
+        self.size = Some(size);
+        Ok(size)
+    }
+
+    pub fn partial(&mut self, path: &Path) -> Result<String> {
+        if let Some(partial) = &self.partial {
+            return Ok(partial.clone());
+        }
+        let size = self.size(path)?;
+        // The edge blocks would cover a small file entirely; hash it once in full.
+        let partial = if size <= 2 * EDGE_BLOCK {
+            self.full(path)?
+        } else {
+            sha256_edges(path, size)?
+        };
+        self.partial = Some(partial.clone());
+        Ok(partial)
+    }
+
+    pub fn full(&mut self, path: &Path) -> Result<String> {
+        if let Some(full) = &self.full {
+            return Ok(full.clone());
+        }
+        let full = sha256_file(path)?;
+        self.full = Some(full.clone());
+        Ok(full)
+    }
+}
+
+/// Whether two files have the same content, comparing the cheap stages first.
+pub fn same_content(a: &Path, b: &Path) -> Result<bool> {
+    let (mut a_hash, mut b_hash) = (FileHash::default(), FileHash::default());
+    Ok(a_hash.size(a)? == b_hash.size(b)?
+        && a_hash.partial(a)? == b_hash.partial(b)?
+        && a_hash.full(a)? == b_hash.full(b)?)
+}
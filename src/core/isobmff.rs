@@ -0,0 +1,374 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use anyhow::Result;
+
+// Seconds between the QuickTime/ISO epoch (1904-01-01) and the Unix epoch.
+const MAC_EPOCH_OFFSET: u64 = 2_082_844_800;
+
+// moov is normally a few hundred KB; anything larger is treated as corrupt.
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+// The HEIF `meta` box lists items and their locations; image data is elsewhere.
+const MAX_META_SIZE: u64 = 16 * 1024 * 1024;
+
+const APPLE_CREATION_DATE_KEY: &[u8] = b"com.apple.quicktime.creationdate";
+
+pub struct BoxIter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for BoxIter<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 8 {
+            return None;
+        }
+        let size = read_u32(self.data, 0)? as u64;
+        let box_type: [u8; 4] = self.data[4..8].try_into().ok()?;
+
+        let (header_len, box_len) = match size {
+            0 => (8, self.data.len() as u64),
+            1 => (16, read_u64(self.data, 8)?),
+            n => (8, n),
+        };
+
+        if box_len < header_len || box_len > self.data.len() as u64 {
+            self.data = &[];
+            return None;
+        }
+
+        let payload = &self.data[header_len as usize..box_len as usize];
+        self.data = &self.data[box_len as usize..];
+        Some((box_type, payload))
+    }
+}
+
+pub fn boxes(data: &[u8]) -> BoxIter<'_> {
+    BoxIter { data }
+}
+
+pub fn find_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes(data).find(|(t, _)| t == box_type).map(|(_, payload)| payload)
+}
+
+pub fn find_path<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    let mut current = data;
+    for box_type in path {
+        current = find_box(current, box_type)?;
+    }
+    Some(current)
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+// `meta` is a full box in ISO files but a plain container in QuickTime files.
+pub fn meta_children(meta: &[u8]) -> &[u8] {
+    if meta.len() >= 4 && meta[0..4] == [0, 0, 0, 0] {
+        &meta[4..]
+    } else {
+        meta
+    }
+}
+
+/// Walks the top-level boxes of `file` without reading their payloads and
+/// returns the payload of the first box of type `box_type`.
+pub fn read_top_level_box(file: &mut File, box_type: &[u8; 4], max_len: u64) -> Result<Option<Vec<u8>>> {
+    let file_len = file.metadata()?.len();
+    let mut pos = 0u64;
+
+    while pos + 8 <= file_len {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header[..8])?;
+
+        let size = u32::from_be_bytes(header[0..4].try_into()?) as u64;
+        let (header_len, box_len) = match size {
+            0 => (8, file_len - pos),
+            1 => {
+                file.read_exact(&mut header[8..16])?;
+                (16, u64::from_be_bytes(header[8..16].try_into()?))
+            }
+            n => (8, n),
+        };
+
+        // 64-bit sizes come straight from the file and may be absurd.
+        let box_end = match pos.checked_add(box_len) {
+            Some(end) if box_len >= header_len && end <= file_len => end,
+            _ => return Ok(None),
+        };
+
+        if &header[4..8] == box_type {
+            let payload_len = box_len - header_len;
+            if payload_len > max_len {
+                return Ok(None);
+            }
+            let mut payload = vec![0u8; payload_len as usize];
+            file.read_exact(&mut payload)?;
+            return Ok(Some(payload));
+        }
+
+        pos = box_end;
+    }
+
+    Ok(None)
+}
+
+// Reads a big-endian field of 0, 2, 4 or 8 bytes, as used by `iloc`.
+fn read_sized(data: &[u8], offset: usize, size: usize) -> Option<u64> {
+    match size {
+        0 => Some(0),
+        2 => Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?) as u64),
+        4 => read_u32(data, offset).map(u64::from),
+        8 => read_u64(data, offset),
+        _ => None,
+    }
+}
+
+// The ID of the first `mime` item with the given content type in `iinf`.
+fn find_mime_item(iinf: &[u8], content_type: &[u8]) -> Option<u32> {
+    let version = *iinf.first()?;
+    let entries = iinf.get(if version == 0 { 6 } else { 8 }..)?;
+
+    boxes(entries).filter(|(t, _)| t == b"infe").find_map(|(_, infe)| {
+        // Only versions 2 and 3 carry an item type.
+        let (item_id, rest) = match infe.first()? {
+            2 => (u16::from_be_bytes(infe.get(4..6)?.try_into().ok()?) as u32, infe.get(6..)?),
+            3 => (read_u32(infe, 4)?, infe.get(8..)?),
+            _ => return None,
+        };
+        // item_protection_index, item_type, then NUL-terminated name and content type.
+        if rest.get(2..6)? != b"mime" {
+            return None;
+        }
+        let mut strings = rest.get(6..)?.split(|&b| b == 0);
+        strings.next()?;
+        (strings.next()? == content_type).then_some(item_id)
+    })
+}
+
+// File extents of `item_id` in `iloc`. Items stored inside the `meta` box
+// (construction method 1 or 2) are not supported.
+fn item_extents(iloc: &[u8], item_id: u32) -> Option<Vec<(u64, u64)>> {
+    let version = *iloc.first()?;
+    let sizes = *iloc.get(4)?;
+    let (offset_size, length_size) = ((sizes >> 4) as usize, (sizes & 0x0f) as usize);
+    let sizes = *iloc.get(5)?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version >= 1 { (sizes & 0x0f) as usize } else { 0 };
+
+    let (item_count, mut pos) = if version < 2 {
+        (u16::from_be_bytes(iloc.get(6..8)?.try_into().ok()?) as u32, 8)
+    } else {
+        (read_u32(iloc, 6)?, 10)
+    };
+
+    for _ in 0..item_count {
+        let id = if version < 2 {
+            pos += 2;
+            read_sized(iloc, pos - 2, 2)? as u32
+        } else {
+            pos += 4;
+            read_u32(iloc, pos - 4)?
+        };
+        let construction_method = if version >= 1 {
+            pos += 2;
+            read_sized(iloc, pos - 2, 2)? & 0x0f
+        } else {
+            0
+        };
+        // data_reference_index
+        pos += 2;
+        let base_offset = read_sized(iloc, pos, base_offset_size)?;
+        pos += base_offset_size;
+        let extent_count = read_sized(iloc, pos, 2)?;
+        pos += 2;
+
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            pos += index_size;
+            let offset = read_sized(iloc, pos, offset_size)?;
+            pos += offset_size;
+            let length = read_sized(iloc, pos, length_size)?;
+            pos += length_size;
+            extents.push((base_offset.checked_add(offset)?, length));
+        }
+
+        if id == item_id {
+            return (construction_method == 0).then_some(extents);
+        }
+    }
+    None
+}
+
+/// Reads the payload of the first `mime` item of `content_type` in a HEIF
+/// file (HEIC/AVIF), e.g. the XMP packet. Items larger than `max_len` are
+/// ignored.
+pub fn read_mime_item(file: &mut File, content_type: &[u8], max_len: u64) -> Result<Option<Vec<u8>>> {
+    let meta = match read_top_level_box(file, b"meta", MAX_META_SIZE)? {
+        Some(meta) => meta,
+        None => return Ok(None),
+    };
+    let children = meta_children(&meta);
+    let extents = find_box(children, b"iinf")
+        .and_then(|iinf| find_mime_item(iinf, content_type))
+        .and_then(|item_id| item_extents(find_box(children, b"iloc")?, item_id));
+    let total_len = |extents: &[(u64, u64)]| extents.iter().try_fold(0u64, |sum, (_, len)| sum.checked_add(*len));
+    let extents = match extents {
+        Some(extents) if total_len(&extents).is_some_and(|len| len <= max_len) => extents,
+        _ => return Ok(None),
+    };
+
+    let mut data = Vec::new();
+    for (offset, length) in extents {
+        file.seek(SeekFrom::Start(offset))?;
+        file.by_ref().take(length).read_to_end(&mut data)?;
+    }
+    Ok(Some(data))
+}
+
+fn mac_time_to_utc(secs: u64) -> Option<DateTime<Utc>> {
+    if secs <= MAC_EPOCH_OFFSET {
+        return None;
+    }
+    Utc.timestamp_opt((secs - MAC_EPOCH_OFFSET) as i64, 0).single()
+}
+
+// mvhd, tkhd and mdhd all start with version/flags followed by creation_time.
+fn header_creation_time(payload: &[u8]) -> Option<DateTime<Utc>> {
+    let version = *payload.first()?;
+    let secs = if version == 1 {
+        read_u64(payload, 4)?
+    } else {
+        read_u32(payload, 4)? as u64
+    };
+    mac_time_to_utc(secs)
+}
+
+fn parse_creation_date_string(s: &str) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim_end_matches('\0').trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt);
+    }
+    for fmt in ["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M:%S%.f%z"] {
+        if let Ok(dt) = DateTime::parse_from_str(s, fmt) {
+            return Some(dt);
+        }
+    }
+    None
+}
+
+// Apple stores metadata as a `keys` table plus an `ilst` whose item types are
+// 1-based indices into that table.
+fn apple_creation_date(moov: &[u8]) -> Option<DateTime<FixedOffset>> {
+    let meta = meta_children(find_box(moov, b"meta")?);
+    let keys = find_box(meta, b"keys")?;
+    let ilst = find_box(meta, b"ilst")?;
+
+    let entry_count = read_u32(keys, 4)?;
+    let mut offset = 8;
+    let mut key_index = None;
+    for index in 1..=entry_count {
+        let size = read_u32(keys, offset)? as usize;
+        if size < 8 || offset + size > keys.len() {
+            return None;
+        }
+        if &keys[offset + 8..offset + size] == APPLE_CREATION_DATE_KEY {
+            key_index = Some(index);
+            break;
+        }
+        offset += size;
+    }
+    let key_index = key_index?;
+
+    let item = boxes(ilst).find(|(t, _)| u32::from_be_bytes(*t) == key_index)?.1;
+    let data = find_box(item, b"data")?;
+    // data payload: type indicator (4), locale (4), value
+    let value = std::str::from_utf8(data.get(8..)?).ok()?;
+    parse_creation_date_string(value)
+}
+
+fn track_creation_date(moov: &[u8], header: &[u8; 4]) -> Option<DateTime<Utc>> {
+    boxes(moov)
+        .filter(|(t, _)| t == b"trak")
+        .find_map(|(_, trak)| {
+            let payload = if header == b"mdhd" {
+                find_path(trak, &[b"mdia", b"mdhd"])?
+            } else {
+                find_box(trak, header)?
+            };
+            header_creation_time(payload)
+        })
+}
+
+/// Returns the recording date stored in an MP4/MOV container, preferring the
+/// Apple creation date key over the movie, track and media headers.
+///
+/// Only the Apple key carries the recording time zone; header times are UTC.
+pub fn read_creation_date(path: &Path) -> Result<Option<DateTime<FixedOffset>>> {
+    let mut file = File::open(path)?;
+    let moov = match read_top_level_box(&mut file, b"moov", MAX_MOOV_SIZE)? {
+        Some(moov) => moov,
+        None => return Ok(None),
+    };
+
+    let date = apple_creation_date(&moov).or_else(|| {
+        find_box(&moov, b"mvhd").and_then(header_creation_time)
+            .or_else(|| track_creation_date(&moov, b"tkhd"))
+            .or_else(|| track_creation_date(&moov, b"mdhd"))
+            .map(|dt| dt.fixed_offset())
+    });
+
+    Ok(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_file(name: &str, data: &[u8]) -> (std::path::PathBuf, File) {
+        let path = std::env::temp_dir().join(format!("isobmff-test-{}-{}.mp4", std::process::id(), name));
+        File::create(&path).unwrap().write_all(data).unwrap();
+        let file = File::open(&path).unwrap();
+        (path, file)
+    }
+
+    #[test]
+    fn stops_at_box_size_that_overflows() {
+        let mut data = vec![0, 0, 0, 8];
+        data.extend_from_slice(b"free");
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(b"skip");
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        data.extend_from_slice(&[0, 0, 0, 8]);
+        data.extend_from_slice(b"moov");
+        let (path, mut file) = temp_file("overflow", &data);
+        assert!(read_top_level_box(&mut file, b"moov", MAX_MOOV_SIZE).unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_item_offset_that_overflows() {
+        // Version 0, offset/length/base offset sizes 8/8/8, one item with one extent.
+        let mut iloc = vec![0, 0, 0, 0, 0x88, 0x80, 0, 1, 0, 7, 0, 0];
+        iloc.extend_from_slice(&u64::MAX.to_be_bytes());
+        iloc.extend_from_slice(&[0, 1]);
+        iloc.extend_from_slice(&1u64.to_be_bytes());
+        iloc.extend_from_slice(&16u64.to_be_bytes());
+        assert_eq!(item_extents(&iloc, 7), None);
+
+        iloc[12..20].copy_from_slice(&100u64.to_be_bytes());
+        assert_eq!(item_extents(&iloc, 7), Some(vec![(101, 16)]));
+    }
+}
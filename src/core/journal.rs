@@ -0,0 +1,306 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::core::hashing::FileHash;
+use crate::core::layout::FolderLayout;
+use crate::core::library_index::{LibraryHashes, STATE_DIR};
+use crate::core::rename::RenameTemplate;
+use crate::core::types::{DateSource, FileType, OrganizePlan, OrganizeSettings, PhotoFile, PlanAction, PlannedFile, TransferMode};
+
+const JOURNAL_DIR: &str = "journal";
+
+/// One line of a run journal. Entries are only ever appended, and each one is
+/// written before the operation it describes. Times are seconds since the Unix
+/// epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalEntry {
+    Started { started: i64, mode: TransferMode },
+    CreateDir { path: PathBuf },
+    Transfer { source: PathBuf, dest: PathBuf, hash: Option<String>, action: TransferMode },
+    // The transfer to `dest` completed.
+    Done { dest: PathBuf },
+    Resumed { resumed: i64 },
+    Finished { finished: i64, cancelled: bool },
+    Undone { undone: i64 },
+}
+
+/// Append-only log of one organize run, stored under
+/// `<target>/.organizer/journal/`.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: fs::File,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedSettings {
+    target_dir: PathBuf,
+    separate_undated: bool,
+    separate_raw: bool,
+    transfer_mode: TransferMode,
+    set_mtime_to_date_taken: bool,
+    folder_layout: String,
+    rename_template: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedFile {
+    path: PathBuf,
+    // Seconds and nanoseconds since the Unix epoch.
+    date_taken: i64,
+    date_taken_nanos: u32,
+    // Seconds east of UTC.
+    utc_offset: Option<i32>,
+    date_source: DateSource,
+    file_type: FileType,
+    size: Option<u64>,
+    partial_hash: Option<String>,
+    hash: Option<String>,
+    sidecars: Vec<PathBuf>,
+    format_mismatch: Option<String>,
+    camera_model: Option<String>,
+}
+
+impl From<&PhotoFile> for SavedFile {
+    fn from(file: &PhotoFile) -> Self {
+        Self {
+            path: file.path.clone(),
+            date_taken: file.date_taken.timestamp(),
+            date_taken_nanos: file.date_taken.timestamp_subsec_nanos(),
+            utc_offset: file.utc_offset.map(|o| o.local_minus_utc()),
+            date_source: file.date_source,
+            file_type: file.file_type.clone(),
+            size: file.hash.size,
+            partial_hash: file.hash.partial.clone(),
+            hash: file.hash.full.clone(),
+            sidecars: file.sidecars.clone(),
+            format_mismatch: file.format_mismatch.clone(),
+            camera_model: file.camera_model.clone(),
+        }
+    }
+}
+
+impl SavedFile {
+    fn into_photo_file(self) -> PhotoFile {
+        PhotoFile {
+            path: self.path,
+            date_taken: Utc.timestamp_opt(self.date_taken, self.date_taken_nanos).single().unwrap_or_default(),
+            utc_offset: self.utc_offset.and_then(FixedOffset::east_opt),
+            date_source: self.date_source,
+            file_type: self.file_type,
+            hash: FileHash { size: self.size, partial: self.partial_hash, full: self.hash },
+            sidecars: self.sidecars,
+            format_mismatch: self.format_mismatch,
+            camera_model: self.camera_model,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedItem {
+    file: SavedFile,
+    action: PlanAction,
+    dest: Option<PathBuf>,
+    reason: Option<String>,
+}
+
+/// The plan of a run, saved next to its journal before anything is
+/// transferred so an interrupted run can be resumed without rescanning.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedPlan {
+    settings: SavedSettings,
+    items: Vec<SavedItem>,
+}
+
+fn plan_path(journal_path: &Path) -> PathBuf {
+    journal_path.with_extension("plan.json")
+}
+
+impl Journal {
+    /// Starts the journal of a new run in `target`.
+    pub fn create(target: &Path, mode: TransferMode) -> Result<Self> {
+        let dir = target.join(STATE_DIR).join(JOURNAL_DIR);
+        fs::create_dir_all(&dir)?;
+
+        let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut path = dir.join(format!("run-{}.jsonl", stamp));
+        let mut counter = 1;
+        while path.exists() {
+            path = dir.join(format!("run-{}-{}.jsonl", stamp, counter));
+            counter += 1;
+        }
+
+        let mut journal = Self::open(&path)?;
+        journal.record(&JournalEntry::Started { started: Utc::now().timestamp(), mode })?;
+        Ok(journal)
+    }
+
+    /// Opens an existing journal for appending. A line left half written by a
+    /// crash is cut off first, so new entries start on a line of their own.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Ok(content) = fs::read(path) {
+            let complete = content.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            if complete < content.len() {
+                fs::OpenOptions::new().write(true).open(path)?.set_len(complete as u64)?;
+            }
+        }
+        let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { path: path.to_path_buf(), file })
+    }
+
+    /// Saves the plan of the run next to the journal.
+    pub fn save_plan(&self, plan: &OrganizePlan) -> Result<()> {
+        let settings = &plan.settings;
+        let saved = SavedPlan {
+            settings: SavedSettings {
+                target_dir: settings.target_dir.clone(),
+                separate_undated: settings.separate_undated,
+                separate_raw: settings.separate_raw,
+                transfer_mode: settings.transfer_mode,
+                set_mtime_to_date_taken: settings.set_mtime_to_date_taken,
+                folder_layout: settings.folder_layout.template().to_string(),
+                rename_template: settings.rename_template.as_ref().map(|t| t.template().to_string()),
+            },
+            items: plan.items.iter().map(|item| SavedItem {
+                file: SavedFile::from(&item.file),
+                action: item.action.clone(),
+                dest: item.dest.clone(),
+                reason: item.reason.clone(),
+            }).collect(),
+        };
+
+        let path = plan_path(&self.path);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string(&saved)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Appends `entry` and flushes it to disk before returning.
+    pub fn record(&mut self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Reads the entries of a journal. A crash can leave a line half written;
+/// lines that do not parse are skipped.
+pub fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+    // Lossy, as a cut can also fall inside a multi-byte character.
+    let content = fs::read(path)?;
+    Ok(String::from_utf8_lossy(&content)
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Reads the plan saved for the run journaled at `journal_path`.
+pub fn read_plan(journal_path: &Path) -> Result<OrganizePlan> {
+    let plan: SavedPlan = serde_json::from_str(&fs::read_to_string(plan_path(journal_path))?)?;
+    let saved = plan.settings;
+    let settings = OrganizeSettings {
+        target_dir: saved.target_dir,
+        dry_run: false,
+        separate_undated: saved.separate_undated,
+        separate_raw: saved.separate_raw,
+        transfer_mode: saved.transfer_mode,
+        set_mtime_to_date_taken: saved.set_mtime_to_date_taken,
+        folder_layout: FolderLayout::parse(&saved.folder_layout)?,
+        rename_template: saved.rename_template.as_deref().map(RenameTemplate::parse).transpose()?,
+    };
+    let items = plan.items.into_iter().map(|item| PlannedFile {
+        file: item.file.into_photo_file(),
+        action: item.action,
+        dest: item.dest,
+        reason: item.reason,
+    }).collect();
+    Ok(OrganizePlan { settings, items, library_hashes: LibraryHashes::default() })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Completed,
+    Cancelled,
+    // No `Finished` entry: the app or machine stopped during the run.
+    Interrupted,
+    Undone,
+}
+
+impl RunStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RunStatus::Completed => "Completed",
+            RunStatus::Cancelled => "Cancelled",
+            RunStatus::Interrupted => "Interrupted",
+            RunStatus::Undone => "Undone",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub journal_path: PathBuf,
+    pub started: DateTime<Utc>,
+    pub mode: TransferMode,
+    pub files_transferred: usize,
+    pub status: RunStatus,
+}
+
+impl RunSummary {
+    /// Whether the run stopped early and its saved plan can finish it.
+    pub fn can_resume(&self) -> bool {
+        self.status == RunStatus::Interrupted && plan_path(&self.journal_path).exists()
+    }
+}
+
+fn summarize(journal_path: &Path) -> Result<Option<RunSummary>> {
+    let entries = read_entries(journal_path)?;
+    let (started, mode) = match entries.first() {
+        Some(JournalEntry::Started { started, mode }) => (Utc.timestamp_opt(*started, 0).single().unwrap_or_default(), *mode),
+        _ => return Ok(None),
+    };
+
+    let mut status = RunStatus::Interrupted;
+    let mut files_transferred = 0;
+    for entry in &entries {
+        match entry {
+            JournalEntry::Done { .. } => files_transferred += 1,
+            JournalEntry::Finished { cancelled: false, .. } => status = RunStatus::Completed,
+            JournalEntry::Finished { cancelled: true, .. } => status = RunStatus::Cancelled,
+            JournalEntry::Undone { .. } => status = RunStatus::Undone,
+            _ => {},
+        }
+    }
+
+    Ok(Some(RunSummary { journal_path: journal_path.to_path_buf(), started, mode, files_transferred, status }))
+}
+
+/// All runs journaled in the library at `target`, newest first.
+pub fn list_runs(target: &Path) -> Vec<RunSummary> {
+    let entries = match fs::read_dir(target.join(STATE_DIR).join(JOURNAL_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut runs: Vec<RunSummary> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|p| summarize(&p).ok().flatten())
+        .collect();
+    runs.sort_by_key(|run| std::cmp::Reverse(run.started));
+    runs
+}
+
+/// The most recent run in `target` that was interrupted and can be resumed.
+pub fn interrupted_run(target: &Path) -> Option<RunSummary> {
+    list_runs(target).into_iter().find(RunSummary::can_resume)
+}
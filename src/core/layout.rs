@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use anyhow::{Result, bail};
+
+/// The layout used before templates existed: `Photos/2024`, `Videos/2024`.
+pub const DEFAULT_FOLDER_LAYOUT: &str = "{category}/{year}";
+
+const UNKNOWN_CAMERA: &str = "Unknown Camera";
+
+// Characters Windows rejects in file and folder names.
+const INVALID_NAME_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\\', '/'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Category,
+    Year,
+    Month,
+    Day,
+    CameraModel,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "category" => Some(Field::Category),
+            "year" => Some(Field::Year),
+            "month" => Some(Field::Month),
+            "day" => Some(Field::Day),
+            "camera_model" => Some(Field::CameraModel),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Field::Year | Field::Month | Field::Day)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    // `width` zero-pads numeric fields, as in `{month:02}`.
+    Field { field: Field, width: usize },
+}
+
+/// A parsed folder template such as `{category}/{year}/{year}-{month:02}`.
+/// Each `/` starts a new folder level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderLayout {
+    template: String,
+    components: Vec<Vec<Segment>>,
+}
+
+fn parse_field(spec: &str) -> Result<Segment> {
+    let (name, format) = match spec.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format.trim())),
+        None => (spec.trim(), None),
+    };
+
+    let field = match Field::parse(name) {
+        Some(field) => field,
+        None => bail!("Unknown placeholder {{{}}}. Use category, year, month, day or camera_model.", name),
+    };
+
+    let width = match format {
+        None => 0,
+        Some(format) => {
+            if !field.is_numeric() {
+                bail!("{{{}}} does not take a format", name);
+            }
+            match format.strip_prefix('0').and_then(|w| w.parse::<usize>().ok()) {
+                Some(width) if (1..=4).contains(&width) => width,
+                _ => bail!("Invalid format \"{}\" for {{{}}}; use a zero-padded width such as :02", format, name),
+            }
+        }
+    };
+
+    Ok(Segment::Field { field, width })
+}
+
+fn parse_component(component: &str) -> Result<Vec<Segment>> {
+    tokenize(component)?
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(text) => Ok(Segment::Literal(text.to_string())),
+            Token::Placeholder(spec) => parse_field(spec),
+        })
+        .collect()
+}
+
+pub enum Token<'a> {
+    Literal(&'a str),
+    // The text between the braces, e.g. `month:02`.
+    Placeholder(&'a str),
+}
+
+/// Splits a file or folder name template into literal text and `{...}`
+/// placeholders, rejecting unbalanced braces and characters that are not
+/// allowed in names.
+pub fn tokenize(text: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('{') {
+            let end = match after.find('}') {
+                Some(end) => end,
+                None => bail!("Unclosed {{ in \"{}\"", text),
+            };
+            tokens.push(Token::Placeholder(&after[..end]));
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('{').unwrap_or(rest.len());
+            let literal = &rest[..end];
+            if literal.contains('}') {
+                bail!("Unmatched }} in \"{}\"", text);
+            }
+            if let Some(c) = literal.chars().find(|c| INVALID_NAME_CHARS.contains(c) || c.is_control()) {
+                bail!("Names cannot contain '{}'", c);
+            }
+            tokens.push(Token::Literal(literal));
+            rest = &rest[end..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Metadata values such as camera models can contain separators; they must not
+// create extra folder levels.
+pub fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| if INVALID_NAME_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+        .collect();
+    cleaned.trim().trim_end_matches('.').to_string()
+}
+
+impl FolderLayout {
+    pub fn parse(template: &str) -> Result<Self> {
+        let template = template.trim();
+        if template.is_empty() {
+            bail!("The folder layout cannot be empty");
+        }
+
+        let mut components = Vec::new();
+        for component in template.split(['/', '\\']) {
+            let component = component.trim();
+            if component.is_empty() {
+                bail!("The folder layout contains an empty folder name");
+            }
+            if component == "." || component == ".." {
+                bail!("The folder layout cannot contain \"{}\"", component);
+            }
+            components.push(parse_component(component)?);
+        }
+
+        Ok(Self { template: template.to_string(), components })
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// The folder, relative to the library root, for a file of the given
+    /// category taken at `date` (local wall-clock time).
+    pub fn render(&self, category: &str, date: NaiveDateTime, camera_model: Option<&str>) -> PathBuf {
+        let mut path = PathBuf::new();
+        for component in &self.components {
+            let mut name = String::new();
+            for segment in component {
+                match segment {
+                    Segment::Literal(text) => name.push_str(text),
+                    Segment::Field { field, width } => {
+                        let value = match field {
+                            Field::Category => category.to_string(),
+                            Field::Year => format!("{:0w$}", date.year(), w = width),
+                            Field::Month => format!("{:0w$}", date.month(), w = width),
+                            Field::Day => format!("{:0w$}", date.day(), w = width),
+                            Field::CameraModel => camera_model
+                                .map(sanitize)
+                                .filter(|m| !m.is_empty())
+                                .unwrap_or_else(|| UNKNOWN_CAMERA.to_string()),
+                        };
+                        name.push_str(&value);
+                    }
+                }
+            }
+            path.push(name);
+        }
+        path
+    }
+
+    /// Example output for the settings screen.
+    pub fn preview(&self) -> PathBuf {
+        let sample = NaiveDate::from_ymd_opt(2024, 7, 4)
+            .and_then(|d| d.and_hms_opt(15, 30, 0))
+            .unwrap_or_default();
+        self.render("Photos", sample, Some("Pixel 8"))
+    }
+}
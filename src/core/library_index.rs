@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use anyhow::{Result, Context};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::core::hashing::FileHash;
+
+/// Folder inside the library that holds the organizer's own state.
+pub const STATE_DIR: &str = ".organizer";
+const INDEX_FILE: &str = "library-index.json";
+const INDEX_VERSION: u32 = 1;
+
+// Files here are kept for review, not part of the library.
+const DUPLICATES_DIR: &str = "Duplicates";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexEntry {
+    size: u64,
+    // Seconds since the Unix epoch; a change invalidates the cached hash.
+    modified: i64,
+    // Filled in the first time a file of the same size is imported.
+    #[serde(default)]
+    partial: Option<String>,
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+impl IndexEntry {
+    fn file_hash(&self) -> FileHash {
+        FileHash { size: Some(self.size), partial: self.partial.clone(), full: self.hash.clone() }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+/// Hashes of library files computed by an index that was not saved, e.g.
+/// while planning, to be carried over to a later index of the same library.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibraryHashes(BTreeMap<String, IndexEntry>);
+
+/// Size and partial and full SHA-256 of every file in the target library, stored under
+/// `<target>/.organizer/` and kept in sync as files are imported.
+#[derive(Debug)]
+pub struct LibraryIndex {
+    root: PathBuf,
+    // Keyed by path relative to the library root, with `/` separators.
+    entries: BTreeMap<String, IndexEntry>,
+    by_size: HashMap<u64, Vec<String>>,
+    // Entries whose hashes were computed since the index was loaded.
+    hashed: HashSet<String>,
+    dirty: bool,
+}
+
+fn modified_secs(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+impl LibraryIndex {
+    fn index_path(root: &Path) -> PathBuf {
+        root.join(STATE_DIR).join(INDEX_FILE)
+    }
+
+    fn read_index_file(root: &Path) -> Result<BTreeMap<String, IndexEntry>> {
+        let path = Self::index_path(root);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let index: IndexFile = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        if index.version != INDEX_VERSION {
+            return Ok(BTreeMap::new());
+        }
+        Ok(index.entries)
+    }
+
+    /// Loads the index of `root` and brings it up to date with the files on
+    /// disk: new files are added, removed ones dropped and changed ones lose
+    /// their cached hash. A missing or unreadable index is rebuilt.
+    pub fn load(root: &Path) -> Self {
+        let mut stored = Self::read_index_file(root).unwrap_or_else(|e| {
+            warn!("Library index in {:?} is unreadable, rebuilding: {}", root, e);
+            BTreeMap::new()
+        });
+        let stored_len = stored.len();
+
+        let mut index = Self { root: root.to_path_buf(), entries: BTreeMap::new(), by_size: HashMap::new(), hashed: HashSet::new(), dirty: false };
+        let walker = WalkDir::new(root).into_iter().filter_entry(|e| {
+            !(e.depth() == 1 && (e.file_name() == STATE_DIR || e.file_name() == DUPLICATES_DIR))
+        });
+
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let meta = match entry.metadata() {
+                Ok(meta) => meta,
+                Err(_) => continue,
+            };
+            let key = match index.key(entry.path()) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let size = meta.len();
+            let modified = modified_secs(&meta);
+            let entry = match stored.remove(&key) {
+                Some(old) if old.size == size && old.modified == modified => old,
+                _ => {
+                    index.dirty = true;
+                    IndexEntry { size, modified, partial: None, hash: None }
+                }
+            };
+            index.add(key, entry);
+        }
+
+        // Entries left over belong to files that no longer exist.
+        if !stored.is_empty() || index.entries.len() != stored_len {
+            index.dirty = true;
+        }
+        index
+    }
+
+    fn key(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn add(&mut self, key: String, entry: IndexEntry) {
+        if let Some(old) = self.entries.get(&key) {
+            if let Some(keys) = self.by_size.get_mut(&old.size) {
+                keys.retain(|k| k != &key);
+            }
+        }
+        self.by_size.entry(entry.size).or_default().push(key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    /// Returns the library file with the same content as `path`, if any.
+    ///
+    /// Only files of the same size are compared, first by their partial hash
+    /// and then in full. `hash` caches the stages computed for `path`.
+    pub fn find_duplicate(&mut self, path: &Path, hash: &mut FileHash) -> Result<Option<PathBuf>> {
+        let candidates = match self.by_size.get(&hash.size(path)?) {
+            Some(keys) if !keys.is_empty() => keys.clone(),
+            _ => return Ok(None),
+        };
+        let source_partial = hash.partial(path)?;
+
+        for key in candidates {
+            let entry = match self.entries.get_mut(&key) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let library_path = self.root.join(&key);
+            let mut library_hash = entry.file_hash();
+
+            let same_content = match library_hash.partial(&library_path).map(|p| p == source_partial) {
+                Ok(true) => {
+                    let source_full = hash.full(path)?;
+                    library_hash.full(&library_path).map(|f| f == source_full)
+                },
+                other => other,
+            };
+
+            if library_hash != entry.file_hash() {
+                entry.partial = library_hash.partial;
+                entry.hash = library_hash.full;
+                self.hashed.insert(key.clone());
+                self.dirty = true;
+            }
+
+            match same_content {
+                Ok(true) => return Ok(Some(library_path)),
+                Ok(false) => {},
+                Err(e) => warn!("Could not hash library file {:?}: {}", library_path, e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The hashes computed since the index was loaded.
+    pub fn new_hashes(&self) -> LibraryHashes {
+        LibraryHashes(self.hashed.iter().filter_map(|key| Some((key.clone(), self.entries.get(key)?.clone()))).collect())
+    }
+
+    /// Takes over hashes computed by another index of this library, for files
+    /// that have not changed since.
+    pub fn add_hashes(&mut self, hashes: &LibraryHashes) {
+        for (key, hashed) in &hashes.0 {
+            if let Some(entry) = self.entries.get_mut(key) {
+                if entry.size == hashed.size && entry.modified == hashed.modified && *entry != *hashed {
+                    *entry = hashed.clone();
+                    self.dirty = true;
+                }
+            }
+        }
+    }
+
+    /// Records a file that was just added to the library.
+    pub fn insert(&mut self, path: &Path, hash: &FileHash) -> Result<()> {
+        let key = self.key(path).with_context(|| format!("{:?} is outside the library", path))?;
+        let meta = fs::metadata(path)?;
+        self.add(key, IndexEntry {
+            size: meta.len(),
+            modified: modified_secs(&meta),
+            partial: hash.partial.clone(),
+            hash: hash.full.clone(),
+        });
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Writes the index if it changed. The file is replaced atomically so an
+    /// interrupted write never leaves a truncated index behind.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = Self::index_path(&self.root);
+        fs::create_dir_all(path.parent().unwrap_or(&self.root))?;
+
+        let index = IndexFile { version: INDEX_VERSION, entries: self.entries.clone() };
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(&index)?)?;
+        fs::rename(&tmp_path, &path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
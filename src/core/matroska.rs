@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use chrono::{DateTime, TimeZone, Utc};
+use anyhow::Result;
+
+const EBML_MAGIC: [u8; 4] = [0x1A, 0x45, 0xDF, 0xA3];
+
+const SEGMENT_ID: u64 = 0x1853_8067;
+const INFO_ID: u64 = 0x1549_A966;
+const DATE_UTC_ID: u64 = 0x4461;
+const CLUSTER_ID: u64 = 0x1F43_B675;
+
+// Segment Info is written before the first cluster, well inside this prefix.
+const HEAD_LEN: u64 = 1024 * 1024;
+
+// DateUTC counts nanoseconds from 2001-01-01T00:00:00 UTC.
+const MATROSKA_EPOCH: i64 = 978_307_200;
+
+// EBML variable-length integer: the position of the first set bit gives the
+// length. IDs keep the marker bit; sizes drop it.
+fn read_vint(data: &[u8], pos: usize, keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *data.get(pos)?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let bytes = data.get(pos..pos + len)?;
+
+    let mut value = if keep_marker { first as u64 } else { (first as u64) & (0xFF >> len) };
+    for &b in &bytes[1..] {
+        value = (value << 8) | b as u64;
+    }
+    Some((value, len))
+}
+
+struct Element<'a> {
+    id: u64,
+    // None for elements of unknown size, which run to the end of their parent.
+    payload: Option<&'a [u8]>,
+    rest: &'a [u8],
+}
+
+fn read_element(data: &[u8]) -> Option<Element<'_>> {
+    let (id, id_len) = read_vint(data, 0, true)?;
+    let (size, size_len) = read_vint(data, id_len, false)?;
+    let start = id_len + size_len;
+
+    let unknown_size = size == (1u64 << (7 * size_len)) - 1;
+    if unknown_size {
+        return Some(Element { id, payload: None, rest: &data[start..] });
+    }
+
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    if end > data.len() {
+        // Truncated by the prefix: only the header is usable.
+        return Some(Element { id, payload: None, rest: &[] });
+    }
+    Some(Element { id, payload: Some(&data[start..end]), rest: &data[end..] })
+}
+
+fn find_child(mut data: &[u8], wanted: u64) -> Option<&[u8]> {
+    while let Some(element) = read_element(data) {
+        if element.id == wanted {
+            return element.payload;
+        }
+        if element.id == CLUSTER_ID {
+            return None;
+        }
+        data = element.payload.map(|_| element.rest)?;
+    }
+    None
+}
+
+fn segment_payload(mut data: &[u8]) -> Option<&[u8]> {
+    while let Some(element) = read_element(data) {
+        if element.id == SEGMENT_ID {
+            // Live recordings leave the segment size unknown.
+            return Some(element.payload.unwrap_or(element.rest));
+        }
+        data = element.payload.map(|_| element.rest)?;
+    }
+    None
+}
+
+fn date_utc(data: &[u8]) -> Option<DateTime<Utc>> {
+    if !data.starts_with(&EBML_MAGIC) {
+        return None;
+    }
+
+    segment_payload(data)
+        .and_then(|segment| find_child(segment, INFO_ID))
+        .and_then(|info| find_child(info, DATE_UTC_ID))
+        .and_then(|value| <[u8; 8]>::try_from(value).ok())
+        .map(i64::from_be_bytes)
+        .and_then(|nanos| {
+            let secs = MATROSKA_EPOCH + nanos.div_euclid(1_000_000_000);
+            Utc.timestamp_opt(secs, nanos.rem_euclid(1_000_000_000) as u32).single()
+        })
+}
+
+/// Reads `Segment/Info/DateUTC` from a Matroska or WebM file. Returns `None`
+/// for other files and for muxers that leave the date out.
+pub fn read_date_utc(path: &Path) -> Result<Option<DateTime<Utc>>> {
+    let mut data = Vec::new();
+    File::open(path)?.take(HEAD_LEN).read_to_end(&mut data)?;
+    Ok(date_utc(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Element with an 8-byte size field, which every reader must accept.
+    fn element(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.push(0x01);
+        out.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn date_element(date: DateTime<Utc>) -> Vec<u8> {
+        let nanos = (date.timestamp() - MATROSKA_EPOCH) * 1_000_000_000;
+        element(&[0x44, 0x61], &nanos.to_be_bytes())
+    }
+
+    fn file_with_segment(segment: Vec<u8>) -> Vec<u8> {
+        let mut data = element(&EBML_MAGIC, &[0x42, 0x82, 0x84, b'w', b'e', b'b', b'm']);
+        data.extend(segment);
+        data
+    }
+
+    #[test]
+    fn reads_date_utc() {
+        let date = Utc.with_ymd_and_hms(2023, 7, 14, 15, 30, 12).unwrap();
+        let mut info = element(&[0x2A, 0xD7, 0xB1], &[0x0F, 0x42, 0x40]);
+        info.extend(date_element(date));
+        let mut segment = element(&[0x11, 0x4D, 0x9B, 0x74], &[0; 4]);
+        segment.extend(element(&[0x15, 0x49, 0xA9, 0x66], &info));
+
+        let data = file_with_segment(element(&[0x18, 0x53, 0x80, 0x67], &segment));
+        assert_eq!(date_utc(&data), Some(date));
+    }
+
+    #[test]
+    fn reads_date_utc_from_segment_of_unknown_size() {
+        let date = Utc.with_ymd_and_hms(2001, 1, 1, 0, 0, 1).unwrap();
+        let mut segment = vec![0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        segment.extend(element(&[0x15, 0x49, 0xA9, 0x66], &date_element(date)));
+
+        assert_eq!(date_utc(&file_with_segment(segment)), Some(date));
+    }
+
+    #[test]
+    fn stops_at_first_cluster() {
+        let date = Utc.with_ymd_and_hms(2023, 7, 14, 15, 30, 12).unwrap();
+        let mut segment = element(&[0x1F, 0x43, 0xB6, 0x75], &[0; 8]);
+        segment.extend(element(&[0x15, 0x49, 0xA9, 0x66], &date_element(date)));
+
+        let data = file_with_segment(element(&[0x18, 0x53, 0x80, 0x67], &segment));
+        assert_eq!(date_utc(&data), None);
+    }
+
+    #[test]
+    fn ignores_other_files() {
+        assert_eq!(date_utc(b"RIFF\0\0\0\0AVI LIST"), None);
+        let info = element(&[0x15, 0x49, 0xA9, 0x66], &element(&[0x7B, 0xA9], b"title"));
+        let data = file_with_segment(element(&[0x18, 0x53, 0x80, 0x67], &info));
+        assert_eq!(date_utc(&data), None);
+    }
+}
@@ -0,0 +1,251 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use anyhow::Result;
+
+use crate::core::mpegts;
+use crate::core::types::FileType;
+
+// Enough for three MPEG-TS packets; every other signature fits in the first 64 bytes.
+const SNIFF_LEN: usize = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaFormat {
+    Jpeg,
+    Png,
+    Heic,
+    Avif,
+    WebP,
+    Gif,
+    Tiff,
+    Raw,
+    Mp4,
+    Mov,
+    Avi,
+    Mkv,
+    Mts,
+}
+
+const AVIF_BRANDS: &[&[u8; 4]] = &[b"avif", b"avis"];
+
+const HEIF_BRANDS: &[&[u8; 4]] = &[b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1"];
+
+// Classic QuickTime files may start without `ftyp`.
+const QUICKTIME_ATOMS: &[&[u8; 4]] = &[b"moov", b"mdat", b"wide", b"free", b"skip", b"pnot"];
+
+impl MediaFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(MediaFormat::Jpeg),
+            "png" => Some(MediaFormat::Png),
+            "heic" => Some(MediaFormat::Heic),
+            "avif" => Some(MediaFormat::Avif),
+            "webp" => Some(MediaFormat::WebP),
+            "gif" => Some(MediaFormat::Gif),
+            "tif" | "tiff" => Some(MediaFormat::Tiff),
+            "cr2" | "cr3" | "nef" | "arw" | "dng" | "raf" | "orf" | "rw2" => Some(MediaFormat::Raw),
+            "mp4" | "m4v" | "3gp" | "3g2" => Some(MediaFormat::Mp4),
+            "mov" => Some(MediaFormat::Mov),
+            "avi" => Some(MediaFormat::Avi),
+            "mkv" | "webm" => Some(MediaFormat::Mkv),
+            "mts" | "m2ts" => Some(MediaFormat::Mts),
+            _ => None,
+        }
+    }
+
+    pub fn file_type(&self) -> FileType {
+        match self {
+            MediaFormat::Jpeg | MediaFormat::Png | MediaFormat::Heic | MediaFormat::Avif
+            | MediaFormat::WebP | MediaFormat::Gif | MediaFormat::Tiff => FileType::Photo,
+            MediaFormat::Raw => FileType::Raw,
+            MediaFormat::Mp4 | MediaFormat::Mov | MediaFormat::Avi | MediaFormat::Mkv
+            | MediaFormat::Mts => FileType::Video,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MediaFormat::Jpeg => "JPEG",
+            MediaFormat::Png => "PNG",
+            MediaFormat::Heic => "HEIC",
+            MediaFormat::Avif => "AVIF",
+            MediaFormat::WebP => "WebP",
+            MediaFormat::Gif => "GIF",
+            MediaFormat::Tiff => "TIFF",
+            MediaFormat::Raw => "RAW",
+            MediaFormat::Mp4 => "MP4",
+            MediaFormat::Mov => "MOV",
+            MediaFormat::Avi => "AVI",
+            MediaFormat::Mkv => "Matroska",
+            MediaFormat::Mts => "MPEG-TS",
+        }
+    }
+}
+
+fn ftyp_format(header: &[u8]) -> MediaFormat {
+    let major: &[u8] = &header[8..12];
+    // Compatible brands follow the major brand and minor version up to the
+    // end of the `ftyp` box.
+    let box_len = (u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize).min(header.len());
+    let compatible = header.get(16..box_len).unwrap_or_default().chunks_exact(4);
+    let brands: Vec<&[u8]> = std::iter::once(major).chain(compatible).collect();
+
+    if major == b"crx " {
+        return MediaFormat::Raw;
+    }
+    // AVIF files also list the generic HEIF brands, so they are checked first.
+    if brands.iter().any(|brand| AVIF_BRANDS.iter().any(|b| &b[..] == *brand)) {
+        return MediaFormat::Avif;
+    }
+
+    let mut is_quicktime = false;
+    for brand in brands {
+        if HEIF_BRANDS.iter().any(|b| b[..] == *brand) {
+            return MediaFormat::Heic;
+        }
+        if brand == b"qt  " {
+            is_quicktime = true;
+        }
+    }
+
+    if is_quicktime { MediaFormat::Mov } else { MediaFormat::Mp4 }
+}
+
+/// Identifies a media file from its leading bytes.
+pub fn detect_format(header: &[u8]) -> Option<MediaFormat> {
+    if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(MediaFormat::Jpeg);
+    }
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(MediaFormat::Png);
+    }
+    if header.starts_with(b"FUJIFILMCCD-RAW") {
+        return Some(MediaFormat::Raw);
+    }
+    // CR2 is TIFF with a "CR" marker; ORF and RW2 use vendor TIFF magic numbers.
+    // Other TIFF-based RAWs (NEF, ARW, DNG) are told apart by extension.
+    if header.len() >= 10 && header.starts_with(b"II*\0") && &header[8..10] == b"CR" {
+        return Some(MediaFormat::Raw);
+    }
+    if header.len() >= 4 && matches!(&header[0..4], b"IIRO" | b"IIRS" | b"IIU\0" | b"MMOR") {
+        return Some(MediaFormat::Raw);
+    }
+    // NEF, ARW and DNG share this signature; the scanner lets their extension
+    // decide.
+    if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+        return Some(MediaFormat::Tiff);
+    }
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return Some(MediaFormat::Gif);
+    }
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"AVI " {
+        return Some(MediaFormat::Avi);
+    }
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+        return Some(MediaFormat::WebP);
+    }
+    if header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Some(MediaFormat::Mkv);
+    }
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        return Some(ftyp_format(header));
+    }
+    if header.len() >= 8 && QUICKTIME_ATOMS.iter().any(|a| a[..] == header[4..8]) {
+        return Some(MediaFormat::Mov);
+    }
+    if mpegts::packet_layout(header).is_some() {
+        return Some(MediaFormat::Mts);
+    }
+    None
+}
+
+pub fn sniff_format(path: &Path) -> Result<Option<MediaFormat>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut header)?;
+    Ok(detect_format(&header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(major: &[u8; 4], compatible: &[&[u8; 4]]) -> Vec<u8> {
+        let mut data = ((16 + 4 * compatible.len()) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(b"ftyp");
+        data.extend_from_slice(major);
+        data.extend_from_slice(&[0; 4]);
+        for brand in compatible {
+            data.extend_from_slice(*brand);
+        }
+        // The next box, which must not be read as a brand.
+        data.extend_from_slice(&[0, 0, 0, 8]);
+        data.extend_from_slice(b"avif");
+        data
+    }
+
+    // Three transport stream packets, each after `prefix` bytes.
+    fn ts_packets(prefix: usize) -> Vec<u8> {
+        let mut packet = vec![0u8; prefix + 188];
+        packet[prefix] = 0x47;
+        packet.repeat(3)
+    }
+
+    #[test]
+    fn detects_iso_media_brands() {
+        assert_eq!(detect_format(&ftyp(b"avif", &[b"avif", b"mif1", b"miaf"])), Some(MediaFormat::Avif));
+        assert_eq!(detect_format(&ftyp(b"mif1", &[b"mif1", b"avis"])), Some(MediaFormat::Avif));
+        assert_eq!(detect_format(&ftyp(b"heic", &[b"mif1", b"heic"])), Some(MediaFormat::Heic));
+        assert_eq!(detect_format(&ftyp(b"mif1", &[b"heic"])), Some(MediaFormat::Heic));
+        assert_eq!(detect_format(&ftyp(b"isom", &[b"isom", b"iso2", b"mp41"])), Some(MediaFormat::Mp4));
+        assert_eq!(detect_format(&ftyp(b"3gp4", &[b"3gp4"])), Some(MediaFormat::Mp4));
+        assert_eq!(detect_format(&ftyp(b"qt  ", &[b"qt  "])), Some(MediaFormat::Mov));
+        assert_eq!(detect_format(&ftyp(b"crx ", &[b"crx "])), Some(MediaFormat::Raw));
+    }
+
+    #[test]
+    fn detects_avif_before_generic_heif_brands() {
+        assert_eq!(detect_format(&ftyp(b"avif", &[b"mif1", b"miaf"])), Some(MediaFormat::Avif));
+        assert_eq!(detect_format(&ftyp(b"avis", &[b"msf1", b"miaf"])), Some(MediaFormat::Avif));
+        assert_eq!(detect_format(&ftyp(b"msf1", &[b"avis"])), Some(MediaFormat::Avif));
+    }
+
+    #[test]
+    fn detects_gif() {
+        assert_eq!(detect_format(b"GIF87a\x01\0\x01\0"), Some(MediaFormat::Gif));
+        assert_eq!(detect_format(b"GIF89a\x01\0\x01\0"), Some(MediaFormat::Gif));
+        assert_eq!(detect_format(b"GIF88a\x01\0\x01\0"), None);
+    }
+
+    #[test]
+    fn detects_tiff() {
+        assert_eq!(detect_format(b"II*\0\x08\0\0\0\0\0"), Some(MediaFormat::Tiff));
+        assert_eq!(detect_format(b"MM\0*\0\0\0\x08\0\0"), Some(MediaFormat::Tiff));
+        // CR2 carries a marker after the TIFF header.
+        assert_eq!(detect_format(b"II*\0\x10\0\0\0CR\x02\0"), Some(MediaFormat::Raw));
+    }
+
+    #[test]
+    fn detects_quicktime_without_ftyp() {
+        assert_eq!(detect_format(b"\0\0\0\x08wide\0\0\0\0mdat"), Some(MediaFormat::Mov));
+    }
+
+    #[test]
+    fn detects_riff_formats() {
+        assert_eq!(detect_format(b"RIFF\x24\0\0\0WEBPVP8X"), Some(MediaFormat::WebP));
+        assert_eq!(detect_format(b"RIFF\x24\0\0\0AVI LIST"), Some(MediaFormat::Avi));
+        assert_eq!(detect_format(b"RIFF\x24\0\0\0WAVEfmt "), None);
+    }
+
+    #[test]
+    fn detects_matroska() {
+        assert_eq!(detect_format(&[0x1A, 0x45, 0xDF, 0xA3, 0x9F, 0x42, 0x86, 0x81, 0x01]), Some(MediaFormat::Mkv));
+    }
+
+    #[test]
+    fn detects_transport_streams() {
+        assert_eq!(detect_format(&ts_packets(0)), Some(MediaFormat::Mts));
+        assert_eq!(detect_format(&ts_packets(4)), Some(MediaFormat::Mts));
+        // A single sync byte is not enough.
+        assert_eq!(detect_format(&ts_packets(0)[..200]), None);
+    }
+}
@@ -2,3 +2,18 @@ pub mod types;
 pub mod scanner;
 pub mod date_utils;
 pub mod organizer;
+pub mod layout;
+pub mod rename;
+pub mod isobmff;
+pub mod riff;
+pub mod filename_date;
+pub mod sidecar;
+pub mod xmp;
+pub mod png;
+pub mod media_format;
+pub mod raw;
+pub mod matroska;
+pub mod mpegts;
+pub mod hashing;
+pub mod library_index;
+pub mod journal;
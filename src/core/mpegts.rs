@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use chrono::{NaiveDate, NaiveDateTime};
+use anyhow::Result;
+
+const TS_PACKET_LEN: usize = 188;
+const SYNC_BYTE: u8 = 0x47;
+
+// The recording time is repeated in the first frames; a few megabytes of
+// stream is plenty to find it.
+const SCAN_LEN: u64 = 8 * 1024 * 1024;
+
+// AVCHD cameras store the recording time in an H.264 SEI "user data
+// unregistered" message: this UUID followed by "MDPM" (modified DV pack meta).
+const MDPM_MARKER: [u8; 20] = [
+    0x17, 0xee, 0x8c, 0x60, 0xf8, 0x4d, 0x11, 0xd9, 0x8c, 0xd6, 0x08, 0x00, 0x20, 0x0c, 0x9a, 0x66,
+    b'M', b'D', b'P', b'M',
+];
+
+const MDPM_DATE: u8 = 0x18;
+const MDPM_TIME: u8 = 0x19;
+
+/// Returns the offset of the first sync byte and the packet stride: 192 for
+/// M2TS (a 4-byte timestamp before each packet), 188 for plain TS.
+pub fn packet_layout(data: &[u8]) -> Option<(usize, usize)> {
+    let synced = |start: usize, stride: usize| {
+        (0..3).all(|i| data.get(start + i * stride) == Some(&SYNC_BYTE))
+    };
+    if synced(4, TS_PACKET_LEN + 4) {
+        Some((4, TS_PACKET_LEN + 4))
+    } else if synced(0, TS_PACKET_LEN) {
+        Some((0, TS_PACKET_LEN))
+    } else {
+        None
+    }
+}
+
+// Reassembles the payload of each PID so an SEI message split across packets
+// is still contiguous.
+fn demux(data: &[u8], start: usize, stride: usize) -> HashMap<u16, Vec<u8>> {
+    let mut streams: HashMap<u16, Vec<u8>> = HashMap::new();
+    let mut pos = start;
+
+    while let Some(packet) = data.get(pos..pos + TS_PACKET_LEN) {
+        pos += stride;
+        if packet[0] != SYNC_BYTE {
+            continue;
+        }
+        let pid = u16::from_be_bytes([packet[1] & 0x1F, packet[2]]);
+        let adaptation = (packet[3] >> 4) & 0x3;
+        if adaptation & 0x1 == 0 {
+            continue;
+        }
+        let payload_start = if adaptation & 0x2 != 0 { 5 + packet[4] as usize } else { 4 };
+        if let Some(payload) = packet.get(payload_start..) {
+            streams.entry(pid).or_default().extend_from_slice(payload);
+        }
+    }
+
+    streams
+}
+
+// H.264 inserts 0x03 after two zero bytes inside NAL units; midnight
+// timestamps hit this.
+fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &b in data {
+        if b == 0x03 && zeros >= 2 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+        out.push(b);
+    }
+    out
+}
+
+fn bcd(b: u8) -> u32 {
+    (b >> 4) as u32 * 10 + (b & 0x0F) as u32
+}
+
+// MDPM: entry count, then (tag, 4 bytes) entries. 0x18 holds a time zone
+// byte, the year and the month; 0x19 the day and time, all BCD.
+fn parse_mdpm(data: &[u8]) -> Option<NaiveDateTime> {
+    let count = *data.first()? as usize;
+    let entries = data.get(1..1 + count * 5)?;
+
+    let mut date = None;
+    let mut time = None;
+    for entry in entries.chunks_exact(5) {
+        match entry[0] {
+            MDPM_DATE => date = Some([entry[1], entry[2], entry[3], entry[4]]),
+            MDPM_TIME => time = Some([entry[1], entry[2], entry[3], entry[4]]),
+            _ => {}
+        }
+    }
+
+    let (date, time) = (date?, time?);
+    let year = (bcd(date[1]) * 100 + bcd(date[2])) as i32;
+    NaiveDate::from_ymd_opt(year, bcd(date[3]), bcd(time[0]))?
+        .and_hms_opt(bcd(time[1]), bcd(time[2]), bcd(time[3]))
+}
+
+/// Reads the AVCHD recording time from an MTS/M2TS stream.
+///
+/// The time zone byte is encoded differently between camera vendors, so the
+/// wall-clock time is returned without an offset.
+pub fn read_recording_date(path: &Path) -> Result<Option<NaiveDateTime>> {
+    let mut data = Vec::new();
+    File::open(path)?.take(SCAN_LEN).read_to_end(&mut data)?;
+    let (start, stride) = match packet_layout(&data) {
+        Some(layout) => layout,
+        None => return Ok(None),
+    };
+
+    let date = demux(&data, start, stride).values().find_map(|stream| {
+        stream
+            .windows(MDPM_MARKER.len())
+            .position(|w| w == MDPM_MARKER)
+            .and_then(|pos| {
+                // 255 entries of 5 bytes plus the count is the most MDPM can hold.
+                let end = (pos + MDPM_MARKER.len() + 1 + 255 * 5).min(stream.len());
+                parse_mdpm(&remove_emulation_prevention(&stream[pos + MDPM_MARKER.len()..end]))
+            })
+    });
+    Ok(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(y, m, d)?.and_hms_opt(h, min, s)
+    }
+
+    #[test]
+    fn parses_mdpm_date_and_time() {
+        let data = [3, 0x18, 0xE2, 0x20, 0x23, 0x07, 0x19, 0x14, 0x15, 0x30, 0x12, 0x70, 0, 0, 0, 0];
+        assert_eq!(parse_mdpm(&data), date(2023, 7, 14, 15, 30, 12));
+    }
+
+    #[test]
+    fn parses_mdpm_after_emulation_prevention() {
+        // Midnight: the encoder inserts 0x03 after the two zero bytes.
+        let stream = [2, 0x18, 0xE2, 0x20, 0x24, 0x01, 0x19, 0x01, 0x00, 0x00, 0x03, 0x05];
+        assert_eq!(parse_mdpm(&remove_emulation_prevention(&stream)), date(2024, 1, 1, 0, 0, 5));
+    }
+
+    #[test]
+    fn needs_date_and_time() {
+        assert_eq!(parse_mdpm(&[1, 0x18, 0xE2, 0x20, 0x23, 0x07]), None);
+        // More entries announced than present.
+        assert_eq!(parse_mdpm(&[2, 0x18, 0xE2, 0x20, 0x23, 0x07]), None);
+    }
+}
@@ -1,12 +1,16 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::io::{Read};
+use std::time::Instant;
 use anyhow::{Result, bail, Context};
-use sha2::{Sha256, Digest};
-use chrono::Datelike;
+use chrono::{Datelike, Local, TimeZone, Utc};
+use filetime::FileTime;
 use log::{info, warn};
 
-use crate::core::types::{PhotoFile, FileType, OrganizationResult, OrganizeSettings};
+use crate::core::hashing::{FileHash, same_content, sha256_file};
+use crate::core::journal::{self, Journal, JournalEntry};
+use crate::core::library_index::LibraryIndex;
+use crate::core::types::{PhotoFile, FileType, OrganizationResult, OrganizeSettings, OrganizePlan, PlannedFile, PlanAction, DateConfidence, TransferMode, ProgressInfo, CancelToken, UndoResult};
 
 pub fn validate_directories(source: &Path, target: &Path) -> Result<()> {
     if !source.exists() {
@@ -34,37 +38,22 @@ pub fn validate_directories(source: &Path, target: &Path) -> Result<()> {
     Ok(())
 }
 
+// Category and date folders depend on the layout and are created as files arrive.
 pub fn create_target_structure(target: &Path) -> Result<()> {
-    fs::create_dir_all(target.join("Photos"))?;
-    fs::create_dir_all(target.join("Videos"))?;
     fs::create_dir_all(target.join("Duplicates"))?;
     Ok(())
 }
 
-fn calculate_file_hash(path: &Path) -> Result<String> {
-    let mut file = fs::File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0; 8192]; 
-
-    loop {
-        let count = file.read(&mut buffer)?;
-        if count == 0 { break; }
-        hasher.update(&buffer[..count]);
-    }
-
-    Ok(format!("{:x}", hasher.finalize()))
-}
-
-fn get_unique_target_path(base_dir: &Path, original_path: &Path) -> PathBuf {
-    let file_name = original_path.file_name().unwrap_or_default();
+// Paths in `planned` are kept free for files planned earlier in the run.
+fn get_unique_target_path(base_dir: &Path, file_name: &Path, planned: &HashSet<PathBuf>) -> PathBuf {
     let mut target_path = base_dir.join(file_name);
     
-    let file_stem = original_path.file_stem().unwrap().to_string_lossy();
-    let extension = original_path.extension().unwrap_or_default().to_string_lossy();
+    let file_stem = file_name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = file_name.extension().unwrap_or_default().to_string_lossy();
     let ext_dot = if extension.is_empty() { "" } else { "." };
 
     let mut counter = 1;
-    while target_path.exists() {
+    while target_path.exists() || planned.contains(&target_path) {
         let new_name = format!("{}_copy_{}{}{}", file_stem, counter, ext_dot, extension);
         target_path = base_dir.join(new_name);
         counter += 1;
@@ -73,91 +62,472 @@ fn get_unique_target_path(base_dir: &Path, original_path: &Path) -> PathBuf {
     target_path
 }
 
-pub fn organize_files(files: Vec<PhotoFile>, settings: &OrganizeSettings) -> OrganizationResult {
-    let mut result = OrganizationResult::default();
-    
-    for file in files {
-        let mut file = file; // Allow move
-        result.processed_files += 1;
+// Lightroom-style sidecars (`IMG_1.xmp`) follow the media stem; others
+// (`IMG_1.jpg.json`) follow the full media file name.
+fn sidecar_target_path(sidecar: &Path, media: &Path, final_dest: &Path) -> PathBuf {
+    let extension = sidecar.extension().unwrap_or_default().to_string_lossy();
+    let base = if sidecar.file_stem() == media.file_stem() {
+        final_dest.file_stem()
+    } else {
+        final_dest.file_name()
+    };
+    final_dest.with_file_name(format!("{}.{}", base.unwrap_or_default().to_string_lossy(), extension))
+}
 
-        let year = file.date_taken.year();
-        let year_folder = year.to_string();
-        
-        let category_folder = match file.file_type {
-            FileType::Photo => "Photos",
-            FileType::Video => "Videos",
-        };
-        
-        let target_year_dir = settings.target_dir.join(category_folder).join(&year_folder);
-        
-        if !settings.dry_run && !target_year_dir.exists() {
-             if let Err(e) = fs::create_dir_all(&target_year_dir) {
-                 result.errors.push(format!("Failed to create dir {:?}: {}", target_year_dir, e));
-                 continue;
-             }
+// `fs::copy` gives the copy a fresh modification time (and on Windows a fresh
+// creation time); put the source's times back.
+fn copy_timestamps(source_meta: &fs::Metadata, dest: &Path) -> std::io::Result<()> {
+    filetime::set_file_times(
+        dest,
+        FileTime::from_last_access_time(source_meta),
+        FileTime::from_last_modification_time(source_meta),
+    )?;
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileTimesExt;
+        let created = source_meta.created()?;
+        let file = fs::OpenOptions::new().write(true).open(dest)?;
+        file.set_times(fs::FileTimes::new().set_created(created))?;
+    }
+
+    Ok(())
+}
+
+// Copies `source` to `dest`, then re-reads the copy and compares its hash with
+// the source. A copy that does not match is removed.
+fn verified_copy(source: &Path, dest: &Path, source_hash: Option<&str>, warnings: &mut Vec<String>) -> std::result::Result<(), String> {
+    // Taken before hashing reads the source, so the access time is the original.
+    let source_meta = fs::metadata(source).map_err(|e| format!("Failed to read {:?}: {}", source, e))?;
+    fs::copy(source, dest).map_err(|e| format!("Failed to copy {:?} to {:?}: {}", source, dest, e))?;
+
+    let source_hash = match source_hash {
+        Some(hash) => hash.to_string(),
+        None => sha256_file(source).map_err(|e| {
+            let _ = fs::remove_file(dest);
+            format!("Failed to hash source {:?} for verification: {}", source, e)
+        })?,
+    };
+
+    match sha256_file(dest) {
+        Ok(dest_hash) if dest_hash == source_hash => {
+            if let Err(e) = copy_timestamps(&source_meta, dest) {
+                warnings.push(format!("Could not preserve timestamps on {:?}: {}", dest, e));
+            }
+            Ok(())
+        },
+        Ok(_) => {
+            let _ = fs::remove_file(dest);
+            Err(format!("Copy of {:?} to {:?} does not match the source (checksum mismatch); the copy was removed", source, dest))
+        },
+        Err(e) => {
+            let _ = fs::remove_file(dest);
+            Err(format!("Failed to verify copy {:?}: {}; the copy was removed", dest, e))
         }
-        
-        let file_name = file.path.file_name().unwrap();
-        let standard_target_path = target_year_dir.join(file_name);
-        
-        let final_dest: PathBuf;
-        let mut is_duplicate = false;
+    }
+}
 
-        if standard_target_path.exists() {
-            let src_hash = match calculate_file_hash(&file.path) {
-                Ok(h) => h,
-                Err(e) => {
-                    result.errors.push(format!("Failed to hash source {:?}: {}", file.path, e));
-                    continue;
+// Puts `source` at `dest` according to `mode`. Problems that still leave the
+// file in place are added to `warnings`; the error describes a failed transfer.
+fn transfer_file(source: &Path, dest: &Path, source_hash: Option<&str>, mode: TransferMode, warnings: &mut Vec<String>) -> std::result::Result<(), String> {
+    match mode {
+        TransferMode::Move => {
+            if let Err(e) = fs::rename(source, dest) {
+                // The source is only deleted once the copy is known to be intact.
+                if let Err(copy_err) = verified_copy(source, dest, source_hash, warnings) {
+                    return Err(format!("Failed to move {:?} to {:?}: {} ({})", source, dest, e, copy_err));
+                }
+                if let Err(del_err) = fs::remove_file(source) {
+                    warnings.push(format!("Copied but failed to delete source {:?}: {}", source, del_err));
                 }
+            }
+        },
+        TransferMode::Copy | TransferMode::Reflink => verified_copy(source, dest, source_hash, warnings)?,
+        TransferMode::HardLink => {
+            // Links cannot cross volumes; a copy still leaves the source untouched.
+            if let Err(e) = fs::hard_link(source, dest) {
+                warnings.push(format!("Could not hard-link {:?} ({}), copied instead", source, e));
+                verified_copy(source, dest, source_hash, warnings)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+// Journals the transfer with the SHA-256 of the source, performs it and marks
+// it done. Nothing is transferred when the source cannot be hashed or the
+// journal entry cannot be written. Returns the hash.
+fn journaled_transfer(source: &Path, dest: &Path, source_hash: Option<&str>, mode: TransferMode, journal: &mut Journal, warnings: &mut Vec<String>) -> std::result::Result<String, String> {
+    let hash = match source_hash {
+        Some(hash) => hash.to_string(),
+        None => sha256_file(source).map_err(|e| format!("Failed to hash {:?}; it was not transferred: {}", source, e))?,
+    };
+    journal
+        .record(&JournalEntry::Transfer {
+            source: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+            hash: Some(hash.clone()),
+            action: mode,
+        })
+        .map_err(|e| format!("Could not write the journal; {:?} was not transferred: {}", source, e))?;
+
+    transfer_file(source, dest, Some(&hash), mode, warnings)?;
+
+    if let Err(e) = journal.record(&JournalEntry::Done { dest: dest.to_path_buf() }) {
+        warnings.push(format!("Could not record the transfer of {:?} in the journal: {}", source, e));
+    }
+    Ok(hash)
+}
+
+// Dates without an offset hold local wall-clock time as if it were UTC; they
+// are taken as this machine's local time, like the camera clock most likely was.
+fn capture_file_time(file: &PhotoFile) -> FileTime {
+    let instant = match file.utc_offset {
+        Some(_) => Some(file.date_taken),
+        None => Local.from_local_datetime(&file.local_date_taken()).earliest().map(|dt| dt.with_timezone(&Utc)),
+    };
+    let instant = instant.unwrap_or(file.date_taken);
+    FileTime::from_unix_time(instant.timestamp(), instant.timestamp_subsec_nanos())
+}
+
+// Creates `dir` and any missing parents, journaling each new folder first so
+// an undo can remove it again.
+fn create_dirs(dir: &Path, journal: &mut Journal) -> Result<()> {
+    let missing: Vec<&Path> = dir.ancestors().take_while(|d| !d.exists()).collect();
+    for dir in missing.into_iter().rev() {
+        journal.record(&JournalEntry::CreateDir { path: dir.to_path_buf() })?;
+        fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+fn transfer_sidecars(file: &PhotoFile, final_dest: &Path, mode: TransferMode, journal: &mut Journal, result: &mut OrganizationResult) {
+    for sidecar in &file.sidecars {
+        // A sidecar shared with another file (e.g. an `-edited` copy) moves with the first one.
+        if !sidecar.exists() {
+            continue;
+        }
+
+        let sidecar_dest = sidecar_target_path(sidecar, &file.path, final_dest);
+        if sidecar_dest.exists() {
+            result.warnings.push(format!("Sidecar {:?} left in source: {:?} already exists", sidecar, sidecar_dest));
+            continue;
+        }
+
+        if let Err(e) = journaled_transfer(sidecar, &sidecar_dest, None, mode, journal, &mut result.warnings) {
+            result.warnings.push(format!("Sidecar not transferred: {}", e));
+        }
+    }
+}
+
+fn category_folder(file_type: &FileType, settings: &OrganizeSettings) -> &'static str {
+    match file_type {
+        FileType::Photo => "Photos",
+        FileType::Raw if settings.separate_raw => "RAW",
+        FileType::Raw => "Photos",
+        FileType::Video => "Videos",
+    }
+}
+
+// Files dated only by a weak source are kept out of the dated folders so they
+// can be reviewed by hand.
+fn routes_to_undated(file: &PhotoFile, settings: &OrganizeSettings) -> bool {
+    settings.separate_undated && file.date_source.confidence() == DateConfidence::Low
+}
+
+/// The folder a file is organized into, before duplicate and name collision
+/// handling.
+pub fn destination_dir(file: &PhotoFile, settings: &OrganizeSettings) -> PathBuf {
+    if routes_to_undated(file, settings) {
+        return settings.target_dir.join("Undated");
+    }
+    let category = category_folder(&file.file_type, settings);
+    let relative = settings.folder_layout.render(category, file.local_date_taken(), file.camera_model.as_deref());
+    settings.target_dir.join(relative)
+}
+
+// New names under the rename template, keyed by source path. `{seq}` counts
+// files that would otherwise get the same name in the same folder, in capture
+// order and then by source path, so the numbers do not depend on scan order.
+fn renamed_file_names(files: &[PhotoFile], settings: &OrganizeSettings) -> HashMap<PathBuf, String> {
+    let template = match &settings.rename_template {
+        Some(template) => template,
+        None => return HashMap::new(),
+    };
+
+    let mut ordered: Vec<&PhotoFile> = files.iter().collect();
+    ordered.sort_by(|a, b| a.date_taken.cmp(&b.date_taken).then_with(|| a.path.cmp(&b.path)));
+
+    let mut counters: HashMap<(PathBuf, String), usize> = HashMap::new();
+    ordered
+        .into_iter()
+        .map(|file| {
+            let seq = if template.uses_seq() {
+                let counter = counters.entry((destination_dir(file, settings), template.render(file, 0))).or_insert(0);
+                *counter += 1;
+                *counter
+            } else {
+                1
             };
-            file.hash = Some(src_hash.clone());
-            
-            let dst_hash = match calculate_file_hash(&standard_target_path) {
-                Ok(h) => h,
+            (file.path.clone(), template.render(file, seq))
+        })
+        .collect()
+}
+
+// Splits `group` by one hashing stage, keeping only subgroups that still
+// contain more than one file. Nothing is kept once `cancel` is set.
+fn split_group(files: &mut [PhotoFile], group: Vec<usize>, stage: fn(&mut FileHash, &Path) -> Result<String>, cancel: &CancelToken) -> Vec<Vec<usize>> {
+    let mut by_hash: HashMap<String, Vec<usize>> = HashMap::new();
+    for i in group {
+        if cancel.is_cancelled() {
+            return Vec::new();
+        }
+        let file = &mut files[i];
+        match stage(&mut file.hash, &file.path) {
+            Ok(hash) => by_hash.entry(hash).or_default().push(i),
+            Err(e) => warn!("Could not hash {:?} ({}), skipping batch duplicate check.", file.path, e),
+        }
+    }
+    by_hash.into_values().filter(|g| g.len() > 1).collect()
+}
+
+// Groups identical files in the batch by size, then by the partial hash and
+// only then by the full hash. Returns each extra copy's source path mapped to
+// the copy that is imported.
+fn batch_duplicates(files: &mut [PhotoFile], cancel: &CancelToken) -> HashMap<PathBuf, PathBuf> {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, file) in files.iter_mut().enumerate() {
+        if let Ok(size) = file.hash.size(&file.path) {
+            by_size.entry(size).or_default().push(i);
+        }
+    }
+
+    let mut duplicates = HashMap::new();
+    for group in by_size.into_values().filter(|g| g.len() > 1) {
+        for group in split_group(files, group, FileHash::partial, cancel) {
+            for mut copies in split_group(files, group, FileHash::full, cancel) {
+                // Identical bytes can still differ in sidecars or file name dates;
+                // keep the copy with the most reliable date, then the first by path.
+                copies.sort_by(|&a, &b| {
+                    files[b].date_source.confidence().cmp(&files[a].date_source.confidence())
+                        .then_with(|| files[a].path.cmp(&files[b].path))
+                });
+                let canonical = files[copies[0]].path.clone();
+                for &i in &copies[1..] {
+                    duplicates.insert(files[i].path.clone(), canonical.clone());
+                }
+            }
+        }
+    }
+
+    duplicates
+}
+
+/// Decides what happens to every file without changing anything. Duplicates
+/// are found within the batch and against the library index, and names are
+/// made unique against the disk and the files planned before them.
+///
+/// Fails with "Planning cancelled" once `cancel` is set.
+pub fn plan_organization(mut files: Vec<PhotoFile>, settings: &OrganizeSettings, cancel: &CancelToken) -> Result<OrganizePlan> {
+    let new_names = renamed_file_names(&files, settings);
+    let mut index = LibraryIndex::load(&settings.target_dir);
+
+    let batch_duplicates = batch_duplicates(&mut files, cancel);
+    // Canonical copies go first so their place in the library is known by the
+    // time their duplicates come up.
+    files.sort_by_key(|file| batch_duplicates.contains_key(&file.path));
+    let mut library_paths: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut planned: HashSet<PathBuf> = HashSet::new();
+    let mut items = Vec::with_capacity(files.len());
+
+    for mut file in files {
+        if cancel.is_cancelled() {
+            bail!("Planning cancelled");
+        }
+
+        let file_name = match new_names.get(&file.path) {
+            Some(name) => PathBuf::from(name),
+            None => PathBuf::from(file.path.file_name().unwrap()),
+        };
+
+        // An identical file earlier in this batch, or any file already in the
+        // library with the same content, wherever it sits and whatever it is called.
+        let batch_original = batch_duplicates.get(&file.path).and_then(|canonical| library_paths.get(canonical)).cloned();
+        let duplicate_of = match batch_original {
+            Some(original) => Some(original),
+            None => match index.find_duplicate(&file.path, &mut file.hash) {
+                Ok(original) => original,
                 Err(e) => {
-                     warn!("Could not hash existing file {:?} ({}), treating as name collision.", standard_target_path, e);
-                     "UNKNOWN".to_string()
+                    let reason = format!("Could not read the file: {}", e);
+                    items.push(PlannedFile { file, action: PlanAction::Skip, dest: None, reason: Some(reason) });
+                    continue;
                 }
-            };
-            
-            if src_hash == dst_hash {
-                is_duplicate = true;
-                final_dest = get_unique_target_path(&settings.target_dir.join("Duplicates"), &file.path);
-            } else {
-                final_dest = get_unique_target_path(&target_year_dir, &file.path);
+            },
+        };
+
+        let (action, dest, reason) = match duplicate_of {
+            Some(original) => {
+                let dest = get_unique_target_path(&settings.target_dir.join("Duplicates"), &file_name, &planned);
+                let reason = format!("Same content as {}", original.display());
+                (PlanAction::Duplicate { original }, dest, Some(reason))
+            },
+            None => {
+                let folder = destination_dir(&file, settings);
+                let dest = get_unique_target_path(&folder, &file_name, &planned);
+                if dest.file_name() != Some(file_name.as_os_str()) {
+                    let reason = format!("{} is already taken in {}", file_name.display(), folder.display());
+                    (PlanAction::Conflict, dest, Some(reason))
+                } else if settings.transfer_mode == TransferMode::Move {
+                    (PlanAction::Move, dest, None)
+                } else {
+                    (PlanAction::Copy, dest, None)
+                }
+            },
+        };
+
+        let library_path = match &action {
+            PlanAction::Duplicate { original } => original.clone(),
+            _ => dest.clone(),
+        };
+        library_paths.insert(file.path.clone(), library_path);
+        planned.insert(dest.clone());
+        items.push(PlannedFile { file, action, dest: Some(dest), reason });
+    }
+
+    // Planning writes nothing; the run saves the library hashes computed
+    // above, so the import and later analyses do not read the same files again.
+    Ok(OrganizePlan { settings: settings.clone(), items, library_hashes: index.new_hashes() })
+}
+
+/// Applies `plan` exactly as it was previewed, calling `progress` before each
+/// file. When `cancel` is set the current file is finished and the rest are
+/// listed in `not_processed`.
+pub fn execute_plan(plan: OrganizePlan, progress: &dyn Fn(ProgressInfo), cancel: &CancelToken) -> OrganizationResult {
+    let mut result = OrganizationResult { transfer_mode: plan.settings.transfer_mode, ..OrganizationResult::default() };
+
+    // Every change to the library is journaled so the run can be undone; a
+    // dry run changes nothing and has no journal. The plan is saved with it
+    // so an interrupted run can be resumed without rescanning.
+    let journal = if plan.settings.dry_run {
+        None
+    } else {
+        let started = Journal::create(&plan.settings.target_dir, plan.settings.transfer_mode)
+            .and_then(|journal| journal.save_plan(&plan).map(|_| journal));
+        match started {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                result.errors.push(format!("Could not start the journal in {:?}; nothing was transferred: {}", plan.settings.target_dir, e));
+                return result;
             }
-        } else {
-            final_dest = standard_target_path;
         }
+    };
+
+    execute(plan, journal, progress, cancel, result)
+}
+
+// Applies the items of a new or resumed run.
+fn execute(plan: OrganizePlan, mut journal: Option<Journal>, progress: &dyn Fn(ProgressInfo), cancel: &CancelToken, mut result: OrganizationResult) -> OrganizationResult {
+    let OrganizePlan { settings, items, library_hashes } = plan;
+    // Loaded again rather than taken from planning: the library can change
+    // while the preview is open, and a resumed run starts in a new session.
+    // Hashes found while planning are kept for files that did not change.
+    let mut index = LibraryIndex::load(&settings.target_dir);
+    index.add_hashes(&library_hashes);
+
+    let started = Instant::now();
+    let mut status = ProgressInfo {
+        files_total: items.len(),
+        bytes_total: items.iter().filter_map(|item| item.file.hash.size).sum(),
+        ..ProgressInfo::default()
+    };
+    
+    let mut items = items.into_iter();
+    while let Some(PlannedFile { mut file, action, dest, reason }) = items.next() {
+        if cancel.is_cancelled() {
+            result.cancelled = true;
+            result.not_processed = std::iter::once(file.path).chain(items.map(|item| item.file.path)).collect();
+            break;
+        }
+
+        status.current_file = Some(file.path.clone());
+        status.elapsed = started.elapsed();
+        progress(status.clone());
+        status.files_done += 1;
+        status.bytes_done += file.hash.size.unwrap_or(0);
+
+        result.processed_files += 1;
+
+        let is_low_confidence = file.date_source.confidence() == DateConfidence::Low;
+        if is_low_confidence {
+            result.low_confidence_dates += 1;
+        }
+
+        let final_dest = match &dest {
+            Some(dest) => dest,
+            None => {
+                result.errors.push(format!("Skipped {:?}: {}", file.path, reason.as_deref().unwrap_or("no destination")));
+                continue;
+            }
+        };
+        let is_duplicate = matches!(action, PlanAction::Duplicate { .. });
         
-        if !settings.dry_run {
+        if let Some(journal) = journal.as_mut() {
+            // The plan found this path free; never replace whatever has
+            // appeared there since.
+            if final_dest.exists() {
+                result.errors.push(format!("{:?} already exists; {:?} was left in the source", final_dest, file.path));
+                continue;
+            }
+
             if let Some(parent) = final_dest.parent() {
-                if !parent.exists() {
-                    let _ = fs::create_dir_all(parent);
+                if let Err(e) = create_dirs(parent, journal) {
+                    result.errors.push(format!("Failed to create dir {:?}: {}", parent, e));
+                    continue;
+                }
+            }
+
+            match journaled_transfer(&file.path, final_dest, file.hash.full.as_deref(), settings.transfer_mode, journal, &mut result.warnings) {
+                Ok(hash) => file.hash.full = Some(hash),
+                Err(e) => {
+                    result.errors.push(e);
+                    continue;
+                }
+            }
+
+            // A hard link shares its times with the source, which must stay untouched.
+            if settings.set_mtime_to_date_taken && settings.transfer_mode != TransferMode::HardLink {
+                if let Err(e) = filetime::set_file_mtime(final_dest, capture_file_time(&file)) {
+                    result.warnings.push(format!("Could not set modification time of {:?}: {}", final_dest, e));
                 }
             }
 
-            if let Err(e) = fs::rename(&file.path, &final_dest) {
-                 if let Err(copy_err) = fs::copy(&file.path, &final_dest) {
-                      result.errors.push(format!("Failed to move {:?} to {:?}: {} (Copy also failed: {})", file.path, final_dest, e, copy_err));
-                      continue;
-                 } else {
-                     if let Err(del_err) = fs::remove_file(&file.path) {
-                          result.warnings.push(format!("Copied but failed to delete source {:?}: {}", file.path, del_err));
-                     }
-                 }
+            if !is_duplicate {
+                if let Err(e) = index.insert(final_dest, &file.hash) {
+                    result.warnings.push(format!("Could not add {:?} to the library index: {}", final_dest, e));
+                }
             }
+
+            transfer_sidecars(&file, final_dest, settings.transfer_mode, journal, &mut result);
         } else {
-            info!("[DRY RUN] Move {:?} -> {:?}", file.path, final_dest);
+            info!("[DRY RUN] {} {:?} -> {:?}", action.label(), file.path, final_dest);
+        }
+
+        if final_dest.file_name() != file.path.file_name() {
+            result.renamed_files.push((file.path.clone(), final_dest.clone()));
         }
 
-        if is_duplicate {
+        if let PlanAction::Duplicate { original } = &action {
             result.duplicates_found += 1;
+            result.duplicates.push((file.path.clone(), original.clone()));
         } else {
+            let year_folder = if routes_to_undated(&file, &settings) {
+                "Undated".to_string()
+            } else {
+                file.local_date_taken().year().to_string()
+            };
             result.moved_files += 1;
             match file.file_type {
-                FileType::Photo => {
+                FileType::Photo | FileType::Raw => {
                     result.photos_moved += 1;
                     *result.photos_per_year.entry(year_folder).or_insert(0) += 1;
                 },
@@ -169,6 +539,509 @@ pub fn organize_files(files: Vec<PhotoFile>, settings: &OrganizeSettings) -> Org
         }
     }
 
+    if let Some(journal) = journal.as_mut() {
+        if let Err(e) = index.save() {
+            result.warnings.push(format!("Could not save the library index: {}", e));
+        }
+        if let Err(e) = journal.record(&JournalEntry::Finished { finished: Utc::now().timestamp(), cancelled: result.cancelled }) {
+            result.warnings.push(format!("Could not finish the journal: {}", e));
+        }
+    }
+
     result
 }
 
+// Settles the transfer that was running when the run stopped. A complete copy
+// is kept and marked done (finishing a move by deleting the source); a partial
+// one is removed so the file is transferred again. Returns whether the file is
+// in the library.
+fn settle_in_flight(source: &Path, dest: &Path, mode: TransferMode, journal: &mut Journal, result: &mut OrganizationResult) -> std::result::Result<bool, String> {
+    if !dest.exists() {
+        return Ok(false);
+    }
+    if source.exists() {
+        match same_content(source, dest) {
+            Ok(true) => {
+                if mode == TransferMode::Move {
+                    if let Err(e) = fs::remove_file(source) {
+                        result.warnings.push(format!("Copied {:?} but could not delete the source: {}", source, e));
+                    }
+                }
+            },
+            Ok(false) => {
+                fs::remove_file(dest).map_err(|e| format!("Failed to remove the incomplete copy {:?}: {}", dest, e))?;
+                result.warnings.push(format!("Removed the incomplete copy {:?} left by the interrupted run", dest));
+                return Ok(false);
+            },
+            Err(e) => return Err(format!("Could not compare {:?} with {:?}: {}", dest, source, e)),
+        }
+    }
+    journal.record(&JournalEntry::Done { dest: dest.to_path_buf() }).map_err(|e| format!("Failed to write the journal: {}", e))?;
+    Ok(true)
+}
+
+/// Finishes an interrupted run from the plan saved with its journal. The
+/// transfer that was in flight is verified first; files the run already
+/// transferred are skipped and the rest of the plan is applied.
+pub fn resume_run(journal_path: &Path, progress: &dyn Fn(ProgressInfo), cancel: &CancelToken) -> Result<OrganizationResult> {
+    let mut plan = journal::read_plan(journal_path).context("Could not read the saved plan")?;
+    let entries = journal::read_entries(journal_path)?;
+    let mut journal = Journal::open(journal_path)?;
+    journal.record(&JournalEntry::Resumed { resumed: Utc::now().timestamp() })?;
+    let mut result = OrganizationResult { transfer_mode: plan.settings.transfer_mode, ..OrganizationResult::default() };
+
+    // Transfers run one at a time, so only the last one can lack its `done`
+    // line without having failed.
+    let mut transferred: HashSet<PathBuf> = HashSet::new();
+    let mut in_flight: Option<(PathBuf, PathBuf)> = None;
+    for entry in entries {
+        match entry {
+            JournalEntry::Transfer { source, dest, .. } => in_flight = Some((source, dest)),
+            JournalEntry::Done { dest } => {
+                if let Some((source, _)) = in_flight.take_if(|(_, d)| *d == dest) {
+                    transferred.insert(source);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    if let Some((source, dest)) = in_flight {
+        match settle_in_flight(&source, &dest, plan.settings.transfer_mode, &mut journal, &mut result) {
+            Ok(true) => {
+                if let Some(item) = plan.items.iter().find(|item| item.file.path == source) {
+                    transfer_sidecars(&item.file, &dest, plan.settings.transfer_mode, &mut journal, &mut result);
+                }
+                transferred.insert(source);
+            },
+            Ok(false) => {},
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    let mut remaining = Vec::new();
+    for item in std::mem::take(&mut plan.items) {
+        if transferred.contains(&item.file.path) {
+            continue;
+        }
+        if item.file.path.exists() {
+            remaining.push(item);
+        } else {
+            result.warnings.push(format!("{:?} is no longer in the source and was skipped", item.file.path));
+        }
+    }
+    plan.items = remaining;
+
+    info!("Resuming {:?}: {} files left", journal_path, plan.items.len());
+    Ok(execute(plan, Some(journal), progress, cancel, result))
+}
+
+// Reverts one journaled transfer whose destination still exists.
+fn undo_transfer(source: &Path, dest: &Path, hash: Option<&str>, action: TransferMode, completed: bool, result: &mut UndoResult) -> std::result::Result<(), String> {
+    if action == TransferMode::Move && completed {
+        // The library file is the only copy. Anything at the source path now
+        // is a different file, e.g. a reused memory card naming a new photo
+        // the same.
+        if source.exists() {
+            result.warnings.push(format!("{:?} was kept: {:?} is taken by another file", dest, source));
+            return Ok(());
+        }
+    } else if source.exists() {
+        // Copies and links, or a move interrupted before the source was
+        // deleted: the source is intact. A completed copy is only deleted
+        // while it still matches the source; one edited since is kept.
+        if completed {
+            match same_content(source, dest) {
+                Ok(true) => {},
+                Ok(false) => {
+                    result.warnings.push(format!("{:?} no longer matches {:?} and was kept", dest, source));
+                    return Ok(());
+                },
+                Err(e) => return Err(format!("Could not compare {:?} with {:?}: {}", dest, source, e)),
+            }
+        }
+        fs::remove_file(dest).map_err(|e| format!("Failed to remove {:?}: {}", dest, e))?;
+        result.removed += 1;
+        return Ok(());
+    } else if action != TransferMode::Move {
+        // The source of a copy or link is gone, e.g. on an ejected card; the
+        // library file may now be the only copy.
+        result.warnings.push(format!("{:?} was kept: its source {:?} is no longer there", dest, source));
+        return Ok(());
+    }
+
+    // Moved: put it back where it came from.
+    if let Some(parent) = source.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to recreate {:?}: {}", parent, e))?;
+    }
+    transfer_file(dest, source, hash, TransferMode::Move, &mut result.warnings)?;
+    result.restored += 1;
+    Ok(())
+}
+
+/// Reverts the run recorded in `journal_path`: moved files go back to their
+/// original paths, copies and links are deleted and folders the run created
+/// are removed once empty. The run is only marked undone when nothing failed.
+pub fn undo_run(journal_path: &Path) -> Result<UndoResult> {
+    let entries = journal::read_entries(journal_path)?;
+    if entries.iter().any(|e| matches!(e, JournalEntry::Undone { .. })) {
+        bail!("This import has already been undone");
+    }
+
+    let completed: HashSet<&PathBuf> = entries
+        .iter()
+        .filter_map(|e| match e {
+            JournalEntry::Done { dest } => Some(dest),
+            _ => None,
+        })
+        .collect();
+
+    let mut result = UndoResult::default();
+    // Newest first, so files leave a folder before the folder is removed.
+    for entry in entries.iter().rev() {
+        match entry {
+            JournalEntry::Transfer { source, dest, hash, action } if dest.exists() => {
+                if let Err(e) = undo_transfer(source, dest, hash.as_deref(), *action, completed.contains(dest), &mut result) {
+                    result.errors.push(e);
+                }
+            },
+            JournalEntry::CreateDir { path } => {
+                // Only succeeds for empty folders; anything added since stays.
+                let _ = fs::remove_dir(path);
+            },
+            _ => {},
+        }
+    }
+
+    // A run with failures stays undoable so the files left can be retried.
+    if result.errors.is_empty() {
+        Journal::open(journal_path)?.record(&JournalEntry::Undone { undone: Utc::now().timestamp() })?;
+    }
+    Ok(result)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use chrono::TimeZone;
+    use crate::core::journal::RunStatus;
+    use crate::core::layout::{DEFAULT_FOLDER_LAYOUT, FolderLayout};
+    use crate::core::types::DateSource;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("organizer-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("source")).unwrap();
+        dir
+    }
+
+    fn settings(target: &Path, mode: TransferMode) -> OrganizeSettings {
+        OrganizeSettings {
+            target_dir: target.to_path_buf(),
+            dry_run: false,
+            separate_undated: false,
+            separate_raw: false,
+            transfer_mode: mode,
+            set_mtime_to_date_taken: false,
+            folder_layout: FolderLayout::parse(DEFAULT_FOLDER_LAYOUT).unwrap(),
+            rename_template: None,
+        }
+    }
+
+    // Writes each file into `<dir>/source`.
+    fn source_files(dir: &Path, files: &[(&str, &str)]) -> Vec<PhotoFile> {
+        files.iter().map(|(name, content)| {
+            let path = dir.join("source").join(name);
+            fs::write(&path, content).unwrap();
+            PhotoFile {
+                path,
+                date_taken: Utc.with_ymd_and_hms(2023, 7, 14, 15, 30, 0).unwrap(),
+                utc_offset: None,
+                date_source: DateSource::ExifOriginal,
+                file_type: FileType::Photo,
+                hash: FileHash::default(),
+                sidecars: Vec::new(),
+                format_mismatch: None,
+                camera_model: None,
+            }
+        }).collect()
+    }
+
+    // Plans importing the files into `<dir>/library`.
+    fn plan(dir: &Path, files: &[(&str, &str)], mode: TransferMode) -> OrganizePlan {
+        plan_organization(source_files(dir, files), &settings(&dir.join("library"), mode), &CancelToken::default()).unwrap()
+    }
+
+    fn library_file(dir: &Path, name: &str) -> PathBuf {
+        dir.join("library").join("Photos").join("2023").join(name)
+    }
+
+    #[test]
+    fn resumes_after_half_written_journal_line() {
+        let dir = temp_dir("half-written");
+        let plan = plan(&dir, &[("a.jpg", "first"), ("b.jpg", "second")], TransferMode::Move);
+
+        // The run moved the first file and crashed while journaling the second.
+        let mut journal = Journal::create(&plan.settings.target_dir, TransferMode::Move).unwrap();
+        journal.save_plan(&plan).unwrap();
+        let first = &plan.items[0];
+        create_dirs(first.dest.as_ref().unwrap().parent().unwrap(), &mut journal).unwrap();
+        journaled_transfer(&first.file.path, first.dest.as_ref().unwrap(), None, TransferMode::Move, &mut journal, &mut Vec::new()).unwrap();
+        let journal_path = journal::list_runs(&plan.settings.target_dir)[0].journal_path.clone();
+        fs::OpenOptions::new().append(true).open(&journal_path).unwrap().write_all(br#"{"kind":"transfer","sou"#).unwrap();
+
+        let result = resume_run(&journal_path, &|_| {}, &CancelToken::default()).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert!(library_file(&dir, "a.jpg").exists());
+        assert!(library_file(&dir, "b.jpg").exists());
+
+        let run = &journal::list_runs(&plan.settings.target_dir)[0];
+        assert_eq!(run.status, RunStatus::Completed);
+        assert_eq!(run.files_transferred, 2);
+
+        let undo = undo_run(&journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!(undo.restored, 2);
+        assert_eq!(fs::read_to_string(dir.join("source").join("a.jpg")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(dir.join("source").join("b.jpg")).unwrap(), "second");
+        assert!(!dir.join("library").join("Photos").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_undo_can_be_retried() {
+        let dir = temp_dir("undo-retry");
+        let plan = plan(&dir, &[("a.jpg", "first")], TransferMode::Move);
+        let target = plan.settings.target_dir.clone();
+        let result = execute_plan(plan, &|_| {}, &CancelToken::default());
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let journal_path = journal::list_runs(&target)[0].journal_path.clone();
+
+        // The source folder cannot be recreated while a file has its name.
+        fs::remove_dir(dir.join("source")).unwrap();
+        fs::write(dir.join("source"), "in the way").unwrap();
+        let undo = undo_run(&journal_path).unwrap();
+        assert_eq!(undo.errors.len(), 1);
+        assert!(library_file(&dir, "a.jpg").exists());
+        assert_eq!(journal::list_runs(&target)[0].status, RunStatus::Completed);
+
+        fs::remove_file(dir.join("source")).unwrap();
+        let undo = undo_run(&journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!(undo.restored, 1);
+        assert_eq!(fs::read_to_string(dir.join("source").join("a.jpg")).unwrap(), "first");
+        assert_eq!(journal::list_runs(&target)[0].status, RunStatus::Undone);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_keeps_copies_whose_source_is_gone() {
+        let dir = temp_dir("undo-copy-gone");
+        let plan = plan(&dir, &[("a.jpg", "first"), ("b.jpg", "second")], TransferMode::Copy);
+        let target = plan.settings.target_dir.clone();
+        let result = execute_plan(plan, &|_| {}, &CancelToken::default());
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        fs::remove_file(dir.join("source").join("a.jpg")).unwrap();
+        let undo = undo_run(&journal::list_runs(&target)[0].journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!((undo.removed, undo.restored, undo.warnings.len()), (1, 0, 1));
+        assert_eq!(fs::read_to_string(library_file(&dir, "a.jpg")).unwrap(), "first");
+        assert!(!library_file(&dir, "b.jpg").exists());
+        assert!(!dir.join("source").join("a.jpg").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn planning_writes_nothing() {
+        let dir = temp_dir("plan-read-only");
+        // Same size as the new file, so planning hashes it.
+        fs::create_dir_all(dir.join("library")).unwrap();
+        fs::write(dir.join("library").join("old.jpg"), "other").unwrap();
+        let plan = plan(&dir, &[("a.jpg", "first")], TransferMode::Copy);
+        let index_path = dir.join("library").join(crate::core::library_index::STATE_DIR).join("library-index.json");
+        assert!(!index_path.exists());
+
+        let result = execute_plan(plan, &|_| {}, &CancelToken::default());
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let index = fs::read_to_string(&index_path).unwrap();
+        assert!(index.contains(&sha256_file(&dir.join("library").join("old.jpg")).unwrap()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn planning_stops_when_cancelled() {
+        let dir = temp_dir("plan-cancel");
+        let files = source_files(&dir, &[("a.jpg", "same"), ("b.jpg", "same")]);
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        assert!(plan_organization(files, &settings(&dir.join("library"), TransferMode::Copy), &cancel).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn capture_time_of_dates_without_offset_is_local() {
+        let dir = temp_dir("mtime");
+        let mut file = source_files(&dir, &[("a.jpg", "first")]).remove(0);
+        let local = Local.with_ymd_and_hms(2023, 7, 14, 15, 30, 0).unwrap();
+        assert_eq!(capture_file_time(&file).unix_seconds(), local.timestamp());
+
+        file.utc_offset = chrono::FixedOffset::east_opt(2 * 3600);
+        assert_eq!(capture_file_time(&file).unix_seconds(), file.date_taken.timestamp());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undoes_a_move() {
+        let dir = temp_dir("undo-move");
+        let plan = plan(&dir, &[("a.jpg", "first"), ("b.jpg", "second")], TransferMode::Move);
+        let target = plan.settings.target_dir.clone();
+        let result = execute_plan(plan, &|_| {}, &CancelToken::default());
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert!(!dir.join("source").join("a.jpg").exists());
+        assert_eq!(fs::read_to_string(library_file(&dir, "a.jpg")).unwrap(), "first");
+
+        let journal_path = journal::list_runs(&target)[0].journal_path.clone();
+        let undo = undo_run(&journal_path).unwrap();
+        assert!(undo.errors.is_empty() && undo.warnings.is_empty(), "{:?} {:?}", undo.errors, undo.warnings);
+        assert_eq!((undo.restored, undo.removed), (2, 0));
+        assert_eq!(fs::read_to_string(dir.join("source").join("a.jpg")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(dir.join("source").join("b.jpg")).unwrap(), "second");
+        // The folders the run created are gone again.
+        assert!(!dir.join("library").join("Photos").exists());
+        assert_eq!(journal::list_runs(&target)[0].status, RunStatus::Undone);
+        assert!(undo_run(&journal_path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_keeps_a_moved_file_whose_source_path_was_reused() {
+        let dir = temp_dir("undo-move-reused");
+        let plan = plan(&dir, &[("a.jpg", "first")], TransferMode::Move);
+        let target = plan.settings.target_dir.clone();
+        execute_plan(plan, &|_| {}, &CancelToken::default());
+
+        fs::write(dir.join("source").join("a.jpg"), "new photo").unwrap();
+        let undo = undo_run(&journal::list_runs(&target)[0].journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!((undo.restored, undo.removed, undo.warnings.len()), (0, 0, 1));
+        assert_eq!(fs::read_to_string(library_file(&dir, "a.jpg")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(dir.join("source").join("a.jpg")).unwrap(), "new photo");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undoes_a_copy() {
+        let dir = temp_dir("undo-copy");
+        let plan = plan(&dir, &[("a.jpg", "first"), ("b.jpg", "second")], TransferMode::Copy);
+        let target = plan.settings.target_dir.clone();
+        let result = execute_plan(plan, &|_| {}, &CancelToken::default());
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(fs::read_to_string(dir.join("source").join("a.jpg")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(library_file(&dir, "a.jpg")).unwrap(), "first");
+
+        // A copy edited in the library is no longer the same file and stays.
+        fs::write(library_file(&dir, "b.jpg"), "edited").unwrap();
+        let undo = undo_run(&journal::list_runs(&target)[0].journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!((undo.restored, undo.removed, undo.warnings.len()), (0, 1, 1));
+        assert!(!library_file(&dir, "a.jpg").exists());
+        assert_eq!(fs::read_to_string(library_file(&dir, "b.jpg")).unwrap(), "edited");
+        assert_eq!(fs::read_to_string(dir.join("source").join("a.jpg")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(dir.join("source").join("b.jpg")).unwrap(), "second");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Plans three files and journals a run that transferred the first and
+    // stopped during the second; `crash` leaves the second file's source and
+    // destination as the crash did. Returns the journal path.
+    fn interrupted_run(dir: &Path, mode: TransferMode, crash: fn(&Path, &Path)) -> PathBuf {
+        let plan = plan(dir, &[("a.jpg", "first"), ("b.jpg", "second"), ("c.jpg", "third")], mode);
+        let mut journal = Journal::create(&plan.settings.target_dir, mode).unwrap();
+        journal.save_plan(&plan).unwrap();
+
+        let first = &plan.items[0];
+        let first_dest = first.dest.as_ref().unwrap();
+        create_dirs(first_dest.parent().unwrap(), &mut journal).unwrap();
+        journaled_transfer(&first.file.path, first_dest, None, mode, &mut journal, &mut Vec::new()).unwrap();
+
+        let second = &plan.items[1];
+        let second_dest = second.dest.as_ref().unwrap();
+        journal.record(&JournalEntry::Transfer {
+            source: second.file.path.clone(),
+            dest: second_dest.clone(),
+            hash: None,
+            action: mode,
+        }).unwrap();
+        crash(&second.file.path, second_dest);
+
+        journal::list_runs(&plan.settings.target_dir)[0].journal_path.clone()
+    }
+
+    fn resume(dir: &Path, journal_path: &Path) -> OrganizationResult {
+        let run = &journal::list_runs(&dir.join("library"))[0];
+        assert!(run.can_resume());
+        let result = resume_run(journal_path, &|_| {}, &CancelToken::default()).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let run = &journal::list_runs(&dir.join("library"))[0];
+        assert_eq!((run.status, run.files_transferred), (RunStatus::Completed, 3));
+        for (name, content) in [("a.jpg", "first"), ("b.jpg", "second"), ("c.jpg", "third")] {
+            assert_eq!(fs::read_to_string(library_file(dir, name)).unwrap(), content);
+        }
+        result
+    }
+
+    #[test]
+    fn resumes_a_move_renamed_before_the_crash() {
+        let dir = temp_dir("resume-renamed");
+        let journal_path = interrupted_run(&dir, TransferMode::Move, |source, dest| fs::rename(source, dest).unwrap());
+        resume(&dir, &journal_path);
+        assert_eq!(fs::read_dir(dir.join("source")).unwrap().count(), 0);
+
+        let undo = undo_run(&journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!(undo.restored, 3);
+        assert_eq!(fs::read_to_string(dir.join("source").join("b.jpg")).unwrap(), "second");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resumes_a_move_copied_but_not_yet_deleted() {
+        let dir = temp_dir("resume-copied");
+        let journal_path = interrupted_run(&dir, TransferMode::Move, |source, dest| { fs::copy(source, dest).unwrap(); });
+        resume(&dir, &journal_path);
+        // The complete copy is kept and the move finished by deleting the source.
+        assert_eq!(fs::read_dir(dir.join("source")).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resumes_a_copy_cut_short() {
+        let dir = temp_dir("resume-partial");
+        let journal_path = interrupted_run(&dir, TransferMode::Copy, |_, dest| fs::write(dest, "sec").unwrap());
+        let result = resume(&dir, &journal_path);
+        assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+        assert_eq!(fs::read_to_string(dir.join("source").join("b.jpg")).unwrap(), "second");
+
+        let undo = undo_run(&journal_path).unwrap();
+        assert!(undo.errors.is_empty(), "{:?}", undo.errors);
+        assert_eq!(undo.removed, 3);
+        assert!(!dir.join("library").join("Photos").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use chrono::{NaiveDate, NaiveDateTime};
+use anyhow::Result;
+
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+// Metadata chunks are small; anything bigger is image data or garbage.
+const MAX_METADATA_CHUNK: u32 = 16 * 1024 * 1024;
+
+const CREATION_TIME_KEYWORD: &[u8] = b"Creation Time";
+const XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+#[derive(Debug, Default)]
+pub struct PngMetadata {
+    pub exif: Option<Vec<u8>>,
+    pub creation_time: Option<String>,
+    // The XMP packet, from the `XML:com.adobe.xmp` iTXt entry.
+    pub xmp: Option<String>,
+    pub modification_time: Option<NaiveDateTime>,
+}
+
+// tEXt: keyword NUL text (Latin-1)
+fn parse_text_chunk(data: &[u8]) -> Option<(&[u8], String)> {
+    let nul = data.iter().position(|&b| b == 0)?;
+    let text = data[nul + 1..].iter().map(|&b| b as char).collect();
+    Some((&data[..nul], text))
+}
+
+// iTXt: keyword NUL flag method language NUL translated NUL text (UTF-8).
+// Compressed text is skipped; creation times are never large enough to be
+// worth compressing.
+fn parse_itxt_chunk(data: &[u8]) -> Option<(&[u8], String)> {
+    let nul = data.iter().position(|&b| b == 0)?;
+    let keyword = &data[..nul];
+    let compressed = *data.get(nul + 1)? != 0;
+    if compressed {
+        return None;
+    }
+    let rest = data.get(nul + 3..)?;
+    let lang_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[lang_end + 1..];
+    let translated_end = rest.iter().position(|&b| b == 0)?;
+    let text = String::from_utf8_lossy(&rest[translated_end + 1..]).into_owned();
+    Some((keyword, text))
+}
+
+// tIME: year (u16 BE), month, day, hour, minute, second, in UTC.
+fn parse_time_chunk(data: &[u8]) -> Option<NaiveDateTime> {
+    if data.len() != 7 {
+        return None;
+    }
+    let year = u16::from_be_bytes([data[0], data[1]]) as i32;
+    NaiveDate::from_ymd_opt(year, data[2] as u32, data[3] as u32)?
+        .and_hms_opt(data[4] as u32, data[5] as u32, data[6] as u32)
+}
+
+/// Walks the chunks of a PNG file, collecting the `eXIf` payload, the
+/// "Creation Time" text entry, the XMP packet and the `tIME` chunk. Image data is skipped
+/// without being read. Returns `None` for files that are not PNGs.
+pub fn read_png_metadata(path: &Path) -> Result<Option<PngMetadata>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut signature = [0u8; 8];
+    if file_len < 8 {
+        return Ok(None);
+    }
+    file.read_exact(&mut signature)?;
+    if &signature != PNG_SIGNATURE {
+        return Ok(None);
+    }
+
+    let mut meta = PngMetadata::default();
+    let mut pos = 8u64;
+
+    while pos + 12 <= file_len {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        let len = u32::from_be_bytes(header[0..4].try_into()?);
+        let chunk_type = &header[4..8];
+
+        if chunk_type == b"IEND" {
+            break;
+        }
+
+        let wanted = matches!(chunk_type, b"eXIf" | b"tEXt" | b"iTXt" | b"tIME");
+        if wanted && len <= MAX_METADATA_CHUNK && pos + 12 + len as u64 <= file_len {
+            let mut data = vec![0u8; len as usize];
+            file.read_exact(&mut data)?;
+
+            match chunk_type {
+                b"eXIf" if meta.exif.is_none() => {
+                    // Some writers keep the JPEG APP1 "Exif\0\0" prefix.
+                    let raw = data.strip_prefix(b"Exif\0\0").unwrap_or(&data);
+                    meta.exif = Some(raw.to_vec());
+                },
+                b"tEXt" | b"iTXt" => {
+                    let entry = if chunk_type == b"tEXt" {
+                        parse_text_chunk(&data)
+                    } else {
+                        parse_itxt_chunk(&data)
+                    };
+                    match entry {
+                        Some((CREATION_TIME_KEYWORD, text)) if meta.creation_time.is_none() => {
+                            meta.creation_time = Some(text.trim().to_string());
+                        },
+                        Some((XMP_KEYWORD, text)) if meta.xmp.is_none() => meta.xmp = Some(text),
+                        _ => {}
+                    }
+                },
+                b"tIME" => meta.modification_time = parse_time_chunk(&data),
+                _ => {}
+            }
+        }
+
+        // length + type + data + CRC
+        pos += 12 + len as u64;
+    }
+
+    Ok(Some(meta))
+}
@@ -0,0 +1,112 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use exif::{Exif, Reader};
+use anyhow::Result;
+
+use crate::core::isobmff;
+
+// IFD0 and the Exif IFD sit at the start of TIFF-based RAW files; the sensor
+// data behind them is tens of megabytes and never needed for dating.
+const TIFF_PREFIX_LEN: u64 = 4 * 1024 * 1024;
+
+const MAX_CR3_MOOV_SIZE: u64 = 16 * 1024 * 1024;
+
+// Canon CR3 metadata lives in moov/uuid with this identifier.
+const CANON_UUID: [u8; 16] = [
+    0x85, 0xc0, 0xb6, 0x87, 0x82, 0x0f, 0x11, 0xe0, 0x81, 0x11, 0xf4, 0xce, 0x46, 0x2b, 0x6a, 0x48,
+];
+
+// Parses a possibly truncated TIFF block, keeping whatever entries are intact.
+fn parse_tiff(data: Vec<u8>) -> Option<Exif> {
+    let mut reader = Reader::new();
+    reader.continue_on_error(true);
+    reader.read_raw(data).or_else(|e| e.distill_partial_result(|_| {})).ok()
+}
+
+fn read_prefix(file: &mut File, len: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.by_ref().take(len).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+// Fujifilm RAF: a fixed header pointing at an embedded JPEG preview that
+// carries the camera EXIF.
+fn read_raf_exif(file: &mut File, header: &[u8]) -> Result<Option<Exif>> {
+    let offset = isobmff::read_u32(header, 84).unwrap_or(0) as u64;
+    let length = isobmff::read_u32(header, 88).unwrap_or(0) as u64;
+    if offset == 0 || length == 0 || offset + length > file.metadata()?.len() {
+        return Ok(None);
+    }
+
+    let mut jpeg = vec![0u8; length as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut jpeg)?;
+    Ok(Reader::new().read_from_container(&mut std::io::Cursor::new(jpeg)).ok())
+}
+
+// Canon CR3: ISO base media with IFD0 (CMT1) and the Exif IFD (CMT2) stored as
+// separate TIFF blocks. The Exif IFD is tried first.
+fn read_cr3_exif(file: &mut File) -> Result<Vec<Exif>> {
+    let moov = match isobmff::read_top_level_box(file, b"moov", MAX_CR3_MOOV_SIZE)? {
+        Some(moov) => moov,
+        None => return Ok(Vec::new()),
+    };
+
+    let canon = isobmff::boxes(&moov)
+        .filter(|(t, _)| t == b"uuid")
+        .find(|(_, payload)| payload.starts_with(&CANON_UUID))
+        .map(|(_, payload)| &payload[16..]);
+    let canon = match canon {
+        Some(canon) => canon,
+        None => return Ok(Vec::new()),
+    };
+
+    let blocks = [b"CMT2", b"CMT1"]
+        .iter()
+        .filter_map(|t| isobmff::find_box(canon, t))
+        .filter_map(|block| parse_tiff(block.to_vec()))
+        .collect();
+    Ok(blocks)
+}
+
+/// Reads the EXIF blocks of a RAW camera file, most specific first. Returns
+/// nothing for files that are not a recognised RAW layout.
+///
+/// Handles TIFF-based formats (CR2, NEF, ARW, DNG), ORF and RW2 (TIFF with a
+/// vendor magic number), RAF and CR3.
+pub fn read_raw_exif(path: &Path) -> Result<Vec<Exif>> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; 96];
+    let header_len = file.read(&mut header)?;
+    let header = &header[..header_len];
+
+    if header.starts_with(b"FUJIFILMCCD-RAW") {
+        return Ok(read_raf_exif(&mut file, header)?.into_iter().collect());
+    }
+
+    if header.len() >= 12 && &header[4..8] == b"ftyp" && &header[8..12] == b"crx " {
+        return read_cr3_exif(&mut file);
+    }
+
+    if header.len() < 4 {
+        return Ok(Vec::new());
+    }
+    let is_tiff = header.starts_with(b"II*\0") || header.starts_with(b"MM\0*");
+    // ORF uses "IIRO"/"IIRS"/"MMOR", RW2 uses "IIU\0"; otherwise plain TIFF.
+    let is_vendor_tiff = matches!(&header[0..4], b"IIRO" | b"IIRS" | b"IIU\0" | b"MMOR");
+    if !is_tiff && !is_vendor_tiff {
+        return Ok(Vec::new());
+    }
+
+    let mut data = read_prefix(&mut file, TIFF_PREFIX_LEN)?;
+    if is_vendor_tiff {
+        if data[0] == b'I' {
+            data[2..4].copy_from_slice(&[0x2A, 0x00]);
+        } else {
+            data[2..4].copy_from_slice(&[0x00, 0x2A]);
+        }
+    }
+    Ok(parse_tiff(data).into_iter().collect())
+}
@@ -0,0 +1,173 @@
+use std::fmt::Write;
+use std::path::Path;
+use chrono::{NaiveDate, NaiveDateTime};
+use anyhow::{Result, bail};
+
+use crate::core::layout::{self, Token};
+use crate::core::types::PhotoFile;
+
+pub const DEFAULT_RENAME_TEMPLATE: &str = "{date:%Y%m%d_%H%M%S}_{camera}_{seq}.{ext}";
+
+const DEFAULT_DATE_FORMAT: &str = "%Y%m%d_%H%M%S";
+const DEFAULT_SEQ_WIDTH: usize = 4;
+const UNKNOWN_CAMERA: &str = "Unknown";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Date(String),
+    Camera,
+    Name,
+    Seq(usize),
+    Ext,
+}
+
+/// A parsed file name template such as
+/// `{date:%Y%m%d_%H%M%S}_{camera}_{seq}.{ext}`.
+///
+/// `{date:...}` takes a chrono/strftime format of the local capture time,
+/// `{camera}` is the EXIF camera model without spaces, `{name}` the original
+/// file stem, `{seq}` the sequence number (`{seq:02}` sets the width) and
+/// `{ext}` the original extension in lowercase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameTemplate {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+fn sample_date() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 7, 4)
+        .and_then(|d| d.and_hms_opt(15, 30, 0))
+        .unwrap_or_default()
+}
+
+fn parse_date_format(format: &str) -> Result<String> {
+    // Invalid or zone-dependent specifiers only fail when formatting.
+    let mut out = String::new();
+    if write!(out, "{}", sample_date().format(format)).is_err() {
+        bail!("Invalid date format \"{}\"", format);
+    }
+    if out.trim().is_empty() {
+        bail!("The date format \"{}\" produces an empty name", format);
+    }
+    Ok(format.to_string())
+}
+
+fn parse_placeholder(spec: &str) -> Result<Segment> {
+    let (name, format) = match spec.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format)),
+        None => (spec.trim(), None),
+    };
+
+    match (name, format) {
+        ("date", None) => Ok(Segment::Date(DEFAULT_DATE_FORMAT.to_string())),
+        ("date", Some(format)) => Ok(Segment::Date(parse_date_format(format)?)),
+        ("seq", None) => Ok(Segment::Seq(DEFAULT_SEQ_WIDTH)),
+        ("seq", Some(format)) => match format.trim().strip_prefix('0').and_then(|w| w.parse::<usize>().ok()) {
+            Some(width) if (1..=9).contains(&width) => Ok(Segment::Seq(width)),
+            _ => bail!("Invalid format \"{}\" for {{seq}}; use a zero-padded width such as :04", format),
+        },
+        ("camera", None) => Ok(Segment::Camera),
+        ("name", None) => Ok(Segment::Name),
+        ("ext", None) => Ok(Segment::Ext),
+        ("camera" | "name" | "ext", Some(_)) => bail!("{{{}}} does not take a format", name),
+        _ => bail!("Unknown placeholder {{{}}}. Use date, camera, name, seq or ext.", name),
+    }
+}
+
+impl RenameTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let template = template.trim();
+        if template.is_empty() {
+            bail!("The rename template cannot be empty");
+        }
+
+        let segments = layout::tokenize(template)?
+            .into_iter()
+            .map(|token| match token {
+                Token::Literal(text) => Ok(Segment::Literal(text.to_string())),
+                Token::Placeholder(spec) => parse_placeholder(spec),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Without the extension, renamed files would no longer open.
+        if !segments.contains(&Segment::Ext) {
+            bail!("The rename template must contain {{ext}}");
+        }
+        Ok(Self { template: template.to_string(), segments })
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn uses_seq(&self) -> bool {
+        self.segments.iter().any(|s| matches!(s, Segment::Seq(_)))
+    }
+
+    fn render_parts(&self, date: NaiveDateTime, camera_model: Option<&str>, original: &Path, seq: usize) -> String {
+        let mut name = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => name.push_str(text),
+                Segment::Date(format) => {
+                    let mut value = String::new();
+                    let _ = write!(value, "{}", date.format(format));
+                    name.push_str(&layout::sanitize(&value));
+                },
+                Segment::Camera => {
+                    let camera = camera_model.map(layout::sanitize).filter(|c| !c.is_empty());
+                    name.push_str(&camera.unwrap_or_else(|| UNKNOWN_CAMERA.to_string()).replace(' ', ""));
+                },
+                Segment::Name => {
+                    name.push_str(&original.file_stem().unwrap_or_default().to_string_lossy());
+                },
+                Segment::Seq(width) => {
+                    let _ = write!(name, "{:0w$}", seq, w = width);
+                },
+                Segment::Ext => {
+                    name.push_str(&original.extension().unwrap_or_default().to_string_lossy().to_lowercase());
+                },
+            }
+        }
+        // A missing extension leaves a trailing dot, which Windows drops.
+        let name = name.trim_end_matches('.');
+        // Placeholders can all be empty for a file, e.g. `{ext}` without an
+        // extension; it then keeps its original name.
+        if name.is_empty() {
+            return original.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        }
+        name.to_string()
+    }
+
+    /// The new file name for `file` with sequence number `seq`.
+    pub fn render(&self, file: &PhotoFile, seq: usize) -> String {
+        self.render_parts(file.local_date_taken(), file.camera_model.as_deref(), &file.path, seq)
+    }
+
+    /// Example output for the settings screen.
+    pub fn preview(&self) -> String {
+        self.render_parts(sample_date(), Some("Pixel 8"), Path::new("IMG_0001.JPG"), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, original: &str) -> String {
+        RenameTemplate::parse(template).unwrap().render_parts(sample_date(), None, Path::new(original), 1)
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        assert_eq!(render("{date:%Y%m%d}_{camera}_{seq:02}.{ext}", "IMG_1.JPG"), "20240704_Unknown_01.jpg");
+        assert_eq!(render("{name}-copy.{ext}", "IMG_1.JPG"), "IMG_1-copy.jpg");
+    }
+
+    #[test]
+    fn keeps_original_name_when_rendered_name_is_empty() {
+        assert_eq!(render("{ext}", "IMG_0001"), "IMG_0001");
+        assert_eq!(render("..{ext}", "IMG_0001"), "IMG_0001");
+    }
+}
@@ -0,0 +1,243 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use anyhow::Result;
+
+// Header lists and metadata chunks are tiny compared to `movi` or image data;
+// larger ones are not worth reading.
+const MAX_LIST_SIZE: u32 = 16 * 1024 * 1024;
+
+// `hdrl` nests one list per stream (`strl`); deeper lists hold no metadata
+// and would only let a crafted file exhaust the stack.
+const MAX_LIST_DEPTH: usize = 3;
+
+#[derive(Debug, Default)]
+pub struct AviMetadata {
+    pub date_time_original: Option<String>,
+    pub creation_date: Option<String>,
+    pub exif: Option<Vec<u8>>,
+}
+
+pub struct ChunkIter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for ChunkIter<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 8 {
+            return None;
+        }
+        let id: [u8; 4] = self.data[0..4].try_into().ok()?;
+        let size = u32::from_le_bytes(self.data[4..8].try_into().ok()?) as usize;
+        let end = 8 + size;
+        if end > self.data.len() {
+            self.data = &[];
+            return None;
+        }
+
+        let payload = &self.data[8..end];
+        // Chunks are padded to an even length.
+        let next = (end + (size & 1)).min(self.data.len());
+        self.data = &self.data[next..];
+        Some((id, payload))
+    }
+}
+
+pub fn chunks(data: &[u8]) -> ChunkIter<'_> {
+    ChunkIter { data }
+}
+
+fn chunk_string(payload: &[u8]) -> Option<String> {
+    let s = String::from_utf8_lossy(payload);
+    let s = s.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if s.is_empty() { None } else { Some(s.to_string()) }
+}
+
+// Some cameras (Pentax, Fujifilm, Nikon) embed a TIFF/EXIF block in the stream
+// header data after a vendor prefix.
+fn find_tiff_block(payload: &[u8]) -> Option<Vec<u8>> {
+    payload
+        .windows(4)
+        .position(|w| w == b"II*\0" || w == b"MM\0*")
+        .map(|start| payload[start..].to_vec())
+}
+
+fn collect_metadata(data: &[u8], depth: usize, meta: &mut AviMetadata) {
+    for (id, payload) in chunks(data) {
+        match &id {
+            b"LIST" if payload.len() >= 4 && depth < MAX_LIST_DEPTH => collect_metadata(&payload[4..], depth + 1, meta),
+            b"IDIT" if meta.date_time_original.is_none() => {
+                meta.date_time_original = chunk_string(payload);
+            },
+            b"ICRD" if meta.creation_date.is_none() => {
+                meta.creation_date = chunk_string(payload);
+            },
+            b"strd" if meta.exif.is_none() => {
+                meta.exif = find_tiff_block(payload);
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Reads the `hdrl` and `INFO` lists of an AVI file, skipping the `movi` data.
+pub fn read_avi_metadata(path: &Path) -> Result<Option<AviMetadata>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut header = [0u8; 12];
+    if file_len < 12 {
+        return Ok(None);
+    }
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"AVI " {
+        return Ok(None);
+    }
+
+    let riff_end = (8 + u32::from_le_bytes(header[4..8].try_into()?) as u64).min(file_len);
+    let mut meta = AviMetadata::default();
+    let mut pos = 12u64;
+
+    while pos + 12 <= riff_end {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk_header = [0u8; 12];
+        file.read_exact(&mut chunk_header)?;
+        let size = u32::from_le_bytes(chunk_header[4..8].try_into()?);
+
+        let is_header_list = &chunk_header[0..4] == b"LIST"
+            && (&chunk_header[8..12] == b"hdrl" || &chunk_header[8..12] == b"INFO");
+        if is_header_list && (4..=MAX_LIST_SIZE).contains(&size) {
+            let mut payload = vec![0u8; size as usize - 4];
+            file.read_exact(&mut payload)?;
+            collect_metadata(&payload, 0, &mut meta);
+        }
+
+        pos += 8 + size as u64 + (size as u64 & 1);
+    }
+
+    Ok(Some(meta))
+}
+
+/// Returns the `EXIF` chunk of a WebP file as a bare TIFF block, dropping the
+/// JPEG "Exif\0\0" prefix some encoders keep. Image data is skipped.
+pub fn read_webp_exif(path: &Path) -> Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut header = [0u8; 12];
+    if file_len < 12 {
+        return Ok(None);
+    }
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WEBP" {
+        return Ok(None);
+    }
+
+    let riff_end = (8 + u32::from_le_bytes(header[4..8].try_into()?) as u64).min(file_len);
+    let mut pos = 12u64;
+
+    while pos + 8 <= riff_end {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk_header = [0u8; 8];
+        file.read_exact(&mut chunk_header)?;
+        let size = u32::from_le_bytes(chunk_header[4..8].try_into()?);
+
+        if &chunk_header[0..4] == b"EXIF" && size <= MAX_LIST_SIZE && pos + 8 + size as u64 <= riff_end {
+            let mut payload = vec![0u8; size as usize];
+            file.read_exact(&mut payload)?;
+            let raw = payload.strip_prefix(b"Exif\0\0").unwrap_or(&payload);
+            return Ok(Some(raw.to_vec()));
+        }
+
+        pos += 8 + size as u64 + (size as u64 & 1);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIFF: &[u8] = b"II*\0\x08\0\0\0\0\0";
+
+    fn chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = id.to_vec();
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            data.push(0);
+        }
+        data
+    }
+
+    fn webp(exif: &[u8]) -> Vec<u8> {
+        // VP8X with an odd-sized chunk before EXIF to exercise the padding.
+        let mut body = b"WEBP".to_vec();
+        body.extend(chunk(b"VP8X", &[0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+        body.extend(chunk(b"ICCP", &[1, 2, 3]));
+        body.extend(chunk(b"EXIF", exif));
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend(body);
+        data
+    }
+
+    fn read_exif(name: &str, data: &[u8]) -> Option<Vec<u8>> {
+        let path = std::env::temp_dir().join(format!("riff-test-{}-{}.webp", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        let exif = read_webp_exif(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        exif
+    }
+
+    fn list(list_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut body = list_type.to_vec();
+        body.extend_from_slice(payload);
+        chunk(b"LIST", &body)
+    }
+
+    #[test]
+    fn reads_stream_lists() {
+        let mut meta = AviMetadata::default();
+        collect_metadata(&list(b"strl", &chunk(b"strd", b"PENTAXII*\0\x08\0")), 0, &mut meta);
+        assert_eq!(meta.exif.as_deref(), Some(&b"II*\0\x08\0"[..]));
+    }
+
+    #[test]
+    fn stops_at_deeply_nested_lists() {
+        const DEPTH: usize = 100_000;
+        let idit = chunk(b"IDIT", b"2023-07-14 15:30:00");
+        let mut data = Vec::with_capacity(DEPTH * 12 + idit.len());
+        for level in 0..DEPTH {
+            data.extend_from_slice(b"LIST");
+            data.extend_from_slice(&((4 + (DEPTH - level - 1) * 12 + idit.len()) as u32).to_le_bytes());
+            data.extend_from_slice(b"JUNK");
+        }
+        data.extend(idit);
+        let mut meta = AviMetadata::default();
+        collect_metadata(&data, 0, &mut meta);
+        assert_eq!(meta.date_time_original, None);
+    }
+
+    #[test]
+    fn reads_bare_webp_exif() {
+        assert_eq!(read_exif("bare", &webp(TIFF)), Some(TIFF.to_vec()));
+    }
+
+    #[test]
+    fn strips_jpeg_exif_prefix() {
+        let mut exif = b"Exif\0\0".to_vec();
+        exif.extend_from_slice(TIFF);
+        assert_eq!(read_exif("prefixed", &webp(&exif)), Some(TIFF.to_vec()));
+    }
+
+    #[test]
+    fn ignores_other_riff_files() {
+        let mut avi = webp(TIFF);
+        avi[8..12].copy_from_slice(b"AVI ");
+        assert_eq!(read_exif("avi", &avi), None);
+    }
+}
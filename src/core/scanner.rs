@@ -1,57 +1,174 @@
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 use walkdir::WalkDir;
-use anyhow::Result;
-use log::error;
+use rayon::prelude::*;
+use anyhow::{Result, bail};
+use log::{error, warn};
 
-use crate::core::types::{PhotoFile, FileType};
+use crate::core::types::{PhotoFile, FileType, ProgressInfo, ScanProgress, CancelToken};
 use crate::core::date_utils::{extract_photo_date, extract_video_date};
+use crate::core::media_format::{MediaFormat, sniff_format};
+use crate::core::sidecar::SidecarFinder;
+use crate::core::hashing::FileHash;
 
-pub fn is_photo(extension: &str) -> bool {
-    matches!(extension.to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "heic")
+// MP4 and MOV share a container and are routinely mislabelled for each other;
+// most RAW formats are TIFF files underneath.
+fn formats_compatible(a: MediaFormat, b: MediaFormat) -> bool {
+    let is_iso = |f| matches!(f, MediaFormat::Mp4 | MediaFormat::Mov);
+    let is_tiff = |f| matches!(f, MediaFormat::Tiff | MediaFormat::Raw);
+    a == b || (is_iso(a) && is_iso(b)) || (is_tiff(a) && is_tiff(b))
 }
 
-pub fn is_video(extension: &str) -> bool {
-    matches!(extension.to_lowercase().as_str(), "mp4" | "mov" | "avi")
+// Camera side files that hold real media data: JPEG thumbnails (`.THM`) and
+// low-resolution proxy videos (GoPro `.LRV`, DJI `.LRF`). They are not imported.
+const CAMERA_SIDE_FILE_EXTENSIONS: &[&str] = &["thm", "lrv", "lrf"];
+
+// The file content decides the format; the extension is only used when the
+// header is not recognised. Returns the format and a note when the two disagree.
+fn classify(path: &Path) -> Option<(MediaFormat, Option<String>)> {
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or_default();
+    if CAMERA_SIDE_FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+        return None;
+    }
+    let hinted = MediaFormat::from_extension(extension);
+    let sniffed = match sniff_format(path) {
+        Ok(format) => format,
+        Err(e) => {
+            warn!("Could not read header of {:?}: {}", path, e);
+            None
+        }
+    };
+
+    match (sniffed, hinted) {
+        (Some(actual), Some(expected)) if !formats_compatible(actual, expected) => {
+            let note = format!("{:?} has a .{} extension but contains {} data", path, extension, actual.name());
+            warn!("{}", note);
+            Some((actual, Some(note)))
+        },
+        (Some(actual), None) => {
+            let note = format!("{:?} has no media extension but contains {} data", path, actual.name());
+            warn!("{}", note);
+            Some((actual, Some(note)))
+        },
+        // Compatible formats: the extension is the more specific of the two.
+        (Some(_), Some(expected)) => Some((expected, None)),
+        (None, Some(expected)) => Some((expected, None)),
+        (None, None) => None,
+    }
 }
 
-pub fn scan_directory(source: &Path) -> Result<Vec<PhotoFile>> {
-    let mut files = Vec::new();
-
-    for entry in WalkDir::new(source).follow_links(true).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                let file_type = if is_photo(ext) {
-                    Some(FileType::Photo)
-                } else if is_video(ext) {
-                    Some(FileType::Video)
-                } else {
-                    None
-                };
-
-                if let Some(ft) = file_type {
-                    let date_extract_res = match ft {
-                        FileType::Photo => extract_photo_date(path),
-                        FileType::Video => extract_video_date(path),
-                    };
-
-                    match date_extract_res {
-                        Ok((date_taken, _warn)) => {
-                             files.push(PhotoFile {
-                                 path: path.to_path_buf(),
-                                 date_taken,
-                                 file_type: ft,
-                                 hash: None, 
-                             });
-                        },
-                        Err(e) => {
-                            error!("Failed to extract date for {:?}: {}", path, e);
-                        }
-                    }
-                }
-            }
+// Walks `source` and pairs every file with its sidecars. Only directory
+// listings are read here; file contents are left to the parallel stage.
+fn discover_files(source: &Path, progress: &(dyn Fn(ScanProgress) + Sync), cancel: &CancelToken) -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let mut sidecar_finder = SidecarFinder::new();
+
+    WalkDir::new(source)
+        .follow_links(true)
+        .into_iter()
+        .take_while(|_| !cancel.is_cancelled())
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.path().is_file())
+        .enumerate()
+        .map(|(i, entry)| {
+            progress(ScanProgress::Discovered(i + 1));
+            let path = entry.into_path();
+            let sidecars = sidecar_finder.find_sidecars(&path);
+            (path, sidecars)
+        })
+        .collect()
+}
+
+fn scan_file(path: PathBuf, sidecars: Vec<PathBuf>) -> Option<PhotoFile> {
+    // XMP and Takeout JSON sidecars are not media themselves; they travel
+    // with the file they describe.
+    let (format, format_mismatch) = classify(&path)?;
+    let ft = format.file_type();
+
+    let date_extract_res = match ft {
+        FileType::Photo | FileType::Raw => extract_photo_date(&path, &sidecars),
+        FileType::Video => extract_video_date(&path, format, &sidecars),
+    };
+
+    match date_extract_res {
+        Ok(date) => Some(PhotoFile {
+            hash: fs::metadata(&path).map(|m| FileHash::with_size(m.len())).unwrap_or_default(),
+            path,
+            date_taken: date.date_taken,
+            utc_offset: date.utc_offset,
+            date_source: date.source,
+            file_type: ft,
+            sidecars,
+            format_mismatch,
+            camera_model: date.camera_model,
+        }),
+        Err(e) => {
+            error!("Failed to extract date for {:?}: {}", path, e);
+            None
         }
     }
-    
+}
+
+/// Finds the media files under `source` and reads their dates. Discovery is
+/// sequential; headers and metadata are read on a pool of `threads` threads
+/// (0 uses one per CPU core). Results keep the directory walk order.
+///
+/// Fails with "Scan cancelled" once `cancel` is set.
+pub fn scan_directory(source: &Path, threads: usize, progress: &(dyn Fn(ScanProgress) + Sync), cancel: &CancelToken) -> Result<Vec<PhotoFile>> {
+    let discovered = discover_files(source, progress, cancel);
+    let files_total = discovered.len();
+    let files_done = AtomicUsize::new(0);
+    let started = Instant::now();
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let files = pool.install(|| {
+        discovered
+            .into_par_iter()
+            .filter_map(|(path, sidecars)| {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+                progress(ScanProgress::Processed(ProgressInfo {
+                    files_done: files_done.fetch_add(1, Ordering::Relaxed),
+                    files_total,
+                    current_file: Some(path.clone()),
+                    elapsed: started.elapsed(),
+                    ..ProgressInfo::default()
+                }));
+                scan_file(path, sidecars)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    if cancel.is_cancelled() {
+        bail!("Scan cancelled");
+    }
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify_file(name: &str, data: &[u8]) -> Option<(MediaFormat, Option<String>)> {
+        let path = std::env::temp_dir().join(format!("scanner-test-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        let format = classify(&path);
+        fs::remove_file(&path).unwrap();
+        format
+    }
+
+    #[test]
+    fn sniffs_files_without_media_extension() {
+        let (format, note) = classify_file("IMG_1.JPG_original", b"\xFF\xD8\xFF\xE1").unwrap();
+        assert_eq!(format, MediaFormat::Jpeg);
+        assert!(note.is_some());
+    }
+
+    #[test]
+    fn skips_camera_side_files() {
+        assert_eq!(classify_file("GOPR0001.THM", b"\xFF\xD8\xFF\xE1"), None);
+        assert_eq!(classify_file("GL010001.LRV", b"\0\0\0\x14ftypmp41\0\0\0\0mp41"), None);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use anyhow::Result;
+
+// Takeout truncates sidecar names to 51 characters including `.json`.
+const TAKEOUT_MAX_NAME_LEN: usize = 51;
+const TAKEOUT_MIN_TRUNCATED_LEN: usize = 46;
+const SUPPLEMENTAL_SUFFIX: &str = "supplemental-metadata";
+
+#[derive(Debug, Deserialize)]
+struct TakeoutTimestamp {
+    timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TakeoutMetadata {
+    photo_taken_time: Option<TakeoutTimestamp>,
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().and_then(|s| s.to_str()).map(|e| e.eq_ignore_ascii_case(extension)).unwrap_or(false)
+}
+
+pub fn is_xmp(path: &Path) -> bool {
+    has_extension(path, "xmp")
+}
+
+// Splits a trailing `(n)` duplicate counter off a name.
+fn split_counter(name: &str) -> (&str, Option<&str>) {
+    if let Some(stripped) = name.strip_suffix(')') {
+        if let Some(open) = stripped.rfind('(') {
+            let digits = &stripped[open + 1..];
+            if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+                return (&name[..open], Some(digits));
+            }
+        }
+    }
+    (name, None)
+}
+
+struct SidecarName {
+    base: String,
+    counter: Option<String>,
+    truncated: bool,
+}
+
+// `IMG_1.jpg.supplemental-metadata(1).json` -> base `IMG_1.jpg`, counter `1`.
+fn parse_sidecar_name(json_name: &str) -> Option<SidecarName> {
+    let without_ext = json_name.strip_suffix(".json").or_else(|| json_name.strip_suffix(".JSON"))?;
+    let (mut base, counter) = split_counter(without_ext);
+
+    // Drop a (possibly truncated) `.supplemental-metadata` suffix, but only
+    // when a media extension is left in front of it.
+    if let Some(dot) = base.rfind('.') {
+        let tail = &base[dot + 1..];
+        if !tail.is_empty() && SUPPLEMENTAL_SUFFIX.starts_with(tail) && base[..dot].contains('.') {
+            base = &base[..dot];
+        }
+    }
+
+    Some(SidecarName {
+        base: base.to_string(),
+        counter: counter.map(str::to_string),
+        truncated: json_name.len() >= TAKEOUT_MIN_TRUNCATED_LEN && json_name.len() <= TAKEOUT_MAX_NAME_LEN,
+    })
+}
+
+// The media names a sidecar may have been written for: `IMG_1(1).jpg` is
+// described by `IMG_1.jpg(1).json`, and `IMG_1-edited.jpg` reuses the
+// original's sidecar.
+fn media_candidates(media_name: &str) -> Vec<(String, Option<String>)> {
+    let (stem, ext) = match media_name.rfind('.') {
+        Some(dot) => (&media_name[..dot], &media_name[dot..]),
+        None => (media_name, ""),
+    };
+    let (stem, counter) = split_counter(stem);
+    let counter = counter.map(str::to_string);
+
+    let mut candidates = vec![(format!("{}{}", stem, ext), counter.clone())];
+    if let Some(original) = stem.strip_suffix("-edited") {
+        candidates.push((format!("{}{}", original, ext), counter));
+    }
+    candidates
+}
+
+fn matches(sidecar: &SidecarName, media_name: &str, counter: &Option<String>) -> Option<usize> {
+    if &sidecar.counter != counter {
+        return None;
+    }
+    let stem = media_name.rsplit_once('.').map(|(s, _)| s).unwrap_or(media_name);
+    if sidecar.base == media_name || sidecar.base == stem {
+        return Some(usize::MAX);
+    }
+    if sidecar.truncated && media_name.starts_with(&sidecar.base) {
+        return Some(sidecar.base.len());
+    }
+    None
+}
+
+/// Locates sidecars (Google Takeout JSON, XMP) next to media files.
+/// Directory listings are cached so each folder is read once per scan.
+#[derive(Default)]
+pub struct SidecarFinder {
+    names_by_dir: HashMap<PathBuf, Vec<String>>,
+}
+
+impl SidecarFinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn sidecar_names(&mut self, dir: &Path) -> &[String] {
+        self.names_by_dir.entry(dir.to_path_buf()).or_insert_with(|| {
+            fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .filter(|e| has_extension(&e.path(), "json") || is_xmp(&e.path()))
+                        .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn find_takeout_sidecar(&mut self, media: &Path) -> Option<PathBuf> {
+        let dir = media.parent()?;
+        let media_name = media.file_name()?.to_str()?;
+        let candidates = media_candidates(media_name);
+
+        let mut best: Option<(usize, &String)> = None;
+        for json_name in self.sidecar_names(dir) {
+            let sidecar = match parse_sidecar_name(json_name) {
+                Some(s) => s,
+                None => continue,
+            };
+            for (name, counter) in &candidates {
+                if let Some(score) = matches(&sidecar, name, counter) {
+                    if best.map(|(b, _)| score > b).unwrap_or(true) {
+                        best = Some((score, json_name));
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, json_name)| dir.join(json_name))
+    }
+
+    /// darktable writes `IMG_1.jpg.xmp`, Lightroom writes `IMG_1.xmp`.
+    pub fn find_xmp_sidecar(&mut self, media: &Path) -> Option<PathBuf> {
+        let dir = media.parent()?;
+        let media_name = media.file_name()?.to_str()?;
+        let media_stem = media.file_stem()?.to_str()?;
+        let full = format!("{}.xmp", media_name);
+        let short = format!("{}.xmp", media_stem);
+
+        let names = self.sidecar_names(dir);
+        names.iter()
+            .find(|n| n.eq_ignore_ascii_case(&full))
+            .or_else(|| names.iter().find(|n| n.eq_ignore_ascii_case(&short)))
+            .map(|n| dir.join(n))
+    }
+
+    pub fn find_sidecars(&mut self, media: &Path) -> Vec<PathBuf> {
+        self.find_xmp_sidecar(media).into_iter()
+            .chain(self.find_takeout_sidecar(media))
+            .collect()
+    }
+}
+
+/// Reads `photoTakenTime` from a Takeout sidecar. The timestamp is UTC.
+pub fn read_takeout_date(path: &Path) -> Result<Option<DateTime<Utc>>> {
+    let content = fs::read_to_string(path)?;
+    let metadata: TakeoutMetadata = serde_json::from_str(&content)?;
+
+    let secs = match metadata.photo_taken_time.and_then(|t| t.timestamp.parse::<i64>().ok()) {
+        Some(secs) if secs > 0 => secs,
+        _ => return Ok(None),
+    };
+    Ok(Utc.timestamp_opt(secs, 0).single())
+}
@@ -1,37 +1,281 @@
 use std::path::PathBuf;
-use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::core::hashing::FileHash;
+use crate::core::layout::FolderLayout;
+use crate::core::library_index::LibraryHashes;
+use crate::core::rename::RenameTemplate;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FileType {
     Photo,
+    Raw,
     Video,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DateSource {
+    ExifOriginal,
+    ExifDateTime,
+    ExifDigitized,
+    ContainerMetadata,
+    ContainerModified,
+    Xmp,
+    Filename,
+    Sidecar,
+    FileMtime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DateConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl DateSource {
+    pub fn confidence(&self) -> DateConfidence {
+        match self {
+            DateSource::ExifOriginal
+            | DateSource::ContainerMetadata
+            | DateSource::Xmp
+            | DateSource::Sidecar => DateConfidence::High,
+            DateSource::ExifDateTime
+            | DateSource::ExifDigitized
+            | DateSource::ContainerModified
+            | DateSource::Filename => DateConfidence::Medium,
+            DateSource::FileMtime => DateConfidence::Low,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateSource::ExifOriginal => "EXIF DateTimeOriginal",
+            DateSource::ExifDateTime => "EXIF DateTime",
+            DateSource::ExifDigitized => "EXIF DateTimeDigitized",
+            DateSource::ContainerMetadata => "Container metadata",
+            DateSource::ContainerModified => "Container modification time",
+            DateSource::Xmp => "Embedded XMP",
+            DateSource::Filename => "File name",
+            DateSource::Sidecar => "Sidecar file",
+            DateSource::FileMtime => "File modification time",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PhotoFile {
     pub path: PathBuf,
     pub date_taken: DateTime<Utc>,
+    pub utc_offset: Option<FixedOffset>,
+    pub date_source: DateSource,
     pub file_type: FileType,
-    pub hash: Option<String>, 
+    pub hash: FileHash,
+    pub sidecars: Vec<PathBuf>,
+    pub format_mismatch: Option<String>,
+    pub camera_model: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+impl PhotoFile {
+    /// Wall-clock time where the file was recorded. Folder placement uses this
+    /// so a shot at 00:30 on New Year's Day stays in the new year; `date_taken`
+    /// stays the true instant for sorting and comparisons.
+    pub fn local_date_taken(&self) -> NaiveDateTime {
+        match self.utc_offset {
+            Some(offset) => self.date_taken.with_timezone(&offset).naive_local(),
+            None => self.date_taken.naive_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TransferMode {
+    #[default]
+    Move,
+    Copy,
+    HardLink,
+    // Copy-on-write clone. The OS copy routine clones blocks where the volume
+    // supports it (ReFS on Windows 11 24H2+, APFS) and copies them elsewhere.
+    Reflink,
+}
+
+impl TransferMode {
+    pub const ALL: [TransferMode; 4] = [TransferMode::Move, TransferMode::Copy, TransferMode::HardLink, TransferMode::Reflink];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransferMode::Move => "Move",
+            TransferMode::Copy => "Copy",
+            TransferMode::HardLink => "Hard link",
+            TransferMode::Reflink => "Reflink (clone)",
+        }
+    }
+
+    /// Whether the source files stay where they are.
+    pub fn keeps_source(&self) -> bool {
+        *self != TransferMode::Move
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct OrganizeSettings {
     pub target_dir: PathBuf,
     pub dry_run: bool, 
+    pub separate_undated: bool,
+    pub separate_raw: bool,
+    pub transfer_mode: TransferMode,
+    pub set_mtime_to_date_taken: bool,
+    pub folder_layout: FolderLayout,
+    // None keeps the original file names.
+    pub rename_template: Option<RenameTemplate>,
+}
+
+/// What happens to one scanned file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlanAction {
+    Move,
+    // Copied, hard-linked or cloned, depending on the transfer mode.
+    Copy,
+    // Same content as `original`, a library file or the copy of this import
+    // that is kept; sent to `Duplicates/`.
+    Duplicate { original: PathBuf },
+    // Left in the source, e.g. because it could not be read.
+    Skip,
+    // The name is taken in the destination folder; imported with a `_copy_N` suffix.
+    Conflict,
+}
+
+impl PlanAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlanAction::Move => "Move",
+            PlanAction::Copy => "Copy",
+            PlanAction::Duplicate { .. } => "Duplicate",
+            PlanAction::Skip => "Skip",
+            PlanAction::Conflict => "Conflict",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedFile {
+    pub file: PhotoFile,
+    pub action: PlanAction,
+    // Final path in the library; None for skipped files.
+    pub dest: Option<PathBuf>,
+    pub reason: Option<String>,
+}
+
+/// Every decision of an organize run, made before anything is changed. The
+/// preview shows it and the organizer applies it as it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrganizePlan {
+    pub settings: OrganizeSettings,
+    pub items: Vec<PlannedFile>,
+    // Library hashes computed while planning; saved to the index by the run.
+    pub library_hashes: LibraryHashes,
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct OrganizationResult {
+    // The mode of the run, which the Settings tab may no longer show.
+    pub transfer_mode: TransferMode,
     pub processed_files: usize,
     pub moved_files: usize,
     pub photos_moved: usize,
     pub videos_moved: usize,
     pub duplicates_found: usize,
+    pub low_confidence_dates: usize,
     pub photos_per_year: BTreeMap<String, usize>,
     pub videos_per_year: BTreeMap<String, usize>,
+    // Source path and final destination of every file whose name changed.
+    pub renamed_files: Vec<(PathBuf, PathBuf)>,
+    // Source path of every duplicate and the library file it duplicates.
+    pub duplicates: Vec<(PathBuf, PathBuf)>,
+    // Set when the run was cancelled; `not_processed` lists the files left untouched.
+    pub cancelled: bool,
+    pub not_processed: Vec<PathBuf>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// A snapshot of a running scan or organize pass.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressInfo {
+    pub files_done: usize,
+    pub files_total: usize,
+    // Bytes transferred; zero while scanning, which only reads headers.
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current_file: Option<PathBuf>,
+    pub elapsed: Duration,
+}
+
+impl ProgressInfo {
+    /// Share of the work done, by bytes when they are counted and by files
+    /// otherwise.
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total > 0 {
+            self.bytes_done as f32 / self.bytes_total as f32
+        } else if self.files_total > 0 {
+            self.files_done as f32 / self.files_total as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Bytes (or files, when bytes are not counted) per second so far.
+    pub fn rate(&self) -> Option<f64> {
+        let secs = self.elapsed.as_secs_f64();
+        if secs < 1.0 {
+            return None;
+        }
+        let done = if self.bytes_total > 0 { self.bytes_done } else { self.files_done as u64 };
+        Some(done as f64 / secs)
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction() as f64;
+        if fraction <= 0.0 || self.elapsed < Duration::from_secs(1) {
+            return None;
+        }
+        Some(self.elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+}
+
+/// Shared flag for stopping a running scan or organize pass. It is checked
+/// between files, so the file in progress is always finished first.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ScanProgress {
+    // Files found so far while walking the source; the total is not known yet.
+    Discovered(usize),
+    Processed(ProgressInfo),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct UndoResult {
+    // Moved files put back at their source path.
+    pub restored: usize,
+    // Copies and links deleted from the library.
+    pub removed: usize,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
 }
@@ -45,6 +289,22 @@ pub struct AppConfig {
     pub last_target_dir: Option<String>,
     pub window_width: Option<f32>,
     pub window_height: Option<f32>,
+    #[serde(default)]
+    pub separate_undated: bool,
+    #[serde(default)]
+    pub separate_raw: bool,
+    #[serde(default)]
+    pub folder_layout: Option<String>,
+    #[serde(default)]
+    pub transfer_mode: TransferMode,
+    #[serde(default)]
+    pub set_mtime_to_date_taken: bool,
+    #[serde(default)]
+    pub rename_files: bool,
+    #[serde(default)]
+    pub rename_template: Option<String>,
+    #[serde(default)]
+    pub scan_threads: usize,
 }
 
 impl AppConfig {
@@ -0,0 +1,103 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use anyhow::Result;
+
+use crate::core::isobmff;
+
+// Embedded packets live in the header area of JPEG, TIFF and RAW files;
+// reading more than this is not worth it for a fallback source.
+const MAX_EMBEDDED_SCAN: u64 = 256 * 1024;
+// HEIF keeps the packet in a metadata item that can sit past the header area.
+const MAX_XMP_ITEM: u64 = 4 * 1024 * 1024;
+const XMP_CONTENT_TYPE: &[u8] = b"application/rdf+xml";
+
+// Most specific first: the capture time, then the IPTC creation date, then
+// the time the file was created by the editing application.
+const DATE_PROPERTIES: &[&str] = &["exif:DateTimeOriginal", "photoshop:DateCreated", "xmp:CreateDate"];
+
+/// XMP dates are ISO 8601 with optional precision and time zone
+/// (`2023`, `2023-07-14`, `2023-07-14T15:30`, `2023-07-14T15:30:12.5+02:00`).
+/// Returns the instant and the offset when one was given.
+fn parse_xmp_date(s: &str) -> Option<(DateTime<Utc>, Option<FixedOffset>)> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some((dt.with_timezone(&Utc), Some(*dt.offset())));
+    }
+    for fmt in ["%Y-%m-%dT%H:%M%:z", "%Y-%m-%dT%H:%M:%S%.f%:z"] {
+        if let Ok(dt) = DateTime::parse_from_str(s, fmt) {
+            return Some((dt.with_timezone(&Utc), Some(*dt.offset())));
+        }
+    }
+
+    let s = s.trim_end_matches('Z');
+    for fmt in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some((Utc.from_utc_datetime(&naive), None));
+        }
+    }
+
+    let date = match s.len() {
+        10 => NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?,
+        7 => NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d").ok()?,
+        4 => NaiveDate::from_ymd_opt(s.parse().ok()?, 1, 1)?,
+        _ => return None,
+    };
+    Some((Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?), None))
+}
+
+// Properties appear either as attributes (`xmp:CreateDate="..."`) or as
+// elements (`<xmp:CreateDate>...</xmp:CreateDate>`).
+fn property_value<'a>(packet: &'a str, name: &str) -> Option<&'a str> {
+    let attr = format!("{}=\"", name);
+    if let Some(start) = packet.find(&attr) {
+        let value = &packet[start + attr.len()..];
+        return value.find('"').map(|end| &value[..end]);
+    }
+
+    let open = format!("<{}>", name);
+    let start = packet.find(&open)? + open.len();
+    let close = format!("</{}>", name);
+    let end = packet[start..].find(&close)? + start;
+    Some(&packet[start..end])
+}
+
+pub fn read_packet_date(packet: &str) -> Option<(DateTime<Utc>, Option<FixedOffset>)> {
+    DATE_PROPERTIES
+        .iter()
+        .find_map(|name| property_value(packet, name).and_then(parse_xmp_date))
+}
+
+fn find_packet(data: &[u8]) -> Option<&str> {
+    let start = find_bytes(data, b"<x:xmpmeta")?;
+    let end = find_bytes(&data[start..], b"</x:xmpmeta>")? + start;
+    std::str::from_utf8(&data[start..end]).ok()
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Reads the date from an embedded XMP packet in the header area of the file
+/// or, for HEIF, its XMP item.
+pub fn read_embedded_date(path: &Path) -> Result<Option<(DateTime<Utc>, Option<FixedOffset>)>> {
+    let mut file = fs::File::open(path)?;
+    let mut data = Vec::new();
+    file.by_ref().take(MAX_EMBEDDED_SCAN).read_to_end(&mut data)?;
+    if let Some(packet) = find_packet(&data) {
+        return Ok(read_packet_date(packet));
+    }
+
+    if data.get(4..8) != Some(b"ftyp") {
+        return Ok(None);
+    }
+    let item = isobmff::read_mime_item(&mut file, XMP_CONTENT_TYPE, MAX_XMP_ITEM)?;
+    Ok(item.as_deref().and_then(find_packet).and_then(read_packet_date))
+}
+
+/// Reads the date from an `.xmp` sidecar file.
+pub fn read_sidecar_date(path: &Path) -> Result<Option<(DateTime<Utc>, Option<FixedOffset>)>> {
+    let content = fs::read_to_string(path)?;
+    Ok(read_packet_date(&content))
+}
@@ -2,14 +2,21 @@ use eframe::{egui, App, Frame};
 use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver};
 use std::thread;
+use std::time::Duration;
 
-use crate::core::types::{PhotoFile, OrganizationResult, OrganizeSettings, AppConfig};
+use crate::core::types::{OrganizationResult, OrganizeSettings, OrganizePlan, PlanAction, AppConfig, DateConfidence, TransferMode, ProgressInfo, ScanProgress, CancelToken, UndoResult};
 use crate::core::scanner::scan_directory;
-use crate::core::organizer::{validate_directories, create_target_structure, organize_files};
+use crate::core::organizer::{validate_directories, create_target_structure, plan_organization, execute_plan, undo_run, resume_run};
+use crate::core::journal::{list_runs, interrupted_run, RunStatus, RunSummary};
+use crate::core::layout::{FolderLayout, DEFAULT_FOLDER_LAYOUT};
+use crate::core::rename::{RenameTemplate, DEFAULT_RENAME_TEMPLATE};
+use anyhow::Context;
+use chrono::Local;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum AppTab {
     Organize,
+    History,
     Settings,
 }
 
@@ -17,7 +24,7 @@ pub enum AppTab {
 pub enum ProcessState {
     Idle,
     Analyzing,
-    AnalyzeComplete(Vec<PhotoFile>), 
+    AnalyzeComplete(OrganizePlan),
     Organizing,
 }
 
@@ -29,31 +36,77 @@ pub struct PhotoOrganizerApp {
     // Configuration
     source_dir: Option<PathBuf>,
     target_dir: Option<PathBuf>,
+    separate_undated: bool,
+    separate_raw: bool,
+    transfer_mode: TransferMode,
+    set_mtime_to_date_taken: bool,
+    folder_layout: String,
+    rename_files: bool,
+    rename_template: String,
+    // 0 lets the scanner use one thread per CPU core.
+    scan_threads: usize,
     
     // Process State
     state: ProcessState,
     scan_receiver: Option<Receiver<ScanUpdate>>,
     organize_receiver: Option<Receiver<OrganizeUpdate>>,
+    // Stops the running scan or organize pass.
+    cancel_token: CancelToken,
+    // Mode of the running organize pass or resume.
+    organizing_mode: TransferMode,
+    undo_receiver: Option<Receiver<Result<UndoResult, String>>>,
     
     // Data
-    found_files: Vec<PhotoFile>,
     organization_result: Option<OrganizationResult>,
+    history: Vec<RunSummary>,
+    undo_result: Option<UndoResult>,
+    confirm_undo: Option<RunSummary>,
+    // An interrupted run found in the target library at startup.
+    resume_offer: Option<RunSummary>,
     show_support_modal: bool,
     
     // UI Feedback
     status_message: String,
     progress: f32,
+    progress_info: Option<ProgressInfo>,
+    files_discovered: usize,
     error_message: Option<String>,
     show_warning_modal: bool,
 }
 
 pub enum ScanUpdate {
-    Complete(Vec<PhotoFile>), 
+    Progress(ScanProgress),
+    // The scan is done and the files are being checked against the library.
+    Planning,
+    Complete(OrganizePlan),
+    Cancelled,
     Error(String),
 }
 
 pub enum OrganizeUpdate {
+    Progress(ProgressInfo),
     Complete(OrganizationResult),
+    Error(String),
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
 }
 
 impl Default for PhotoOrganizerApp {
@@ -63,14 +116,30 @@ impl Default for PhotoOrganizerApp {
             is_welcome_screen: true,
             source_dir: None,
             target_dir: None,
+            separate_undated: false,
+            separate_raw: false,
+            transfer_mode: TransferMode::Move,
+            set_mtime_to_date_taken: false,
+            folder_layout: DEFAULT_FOLDER_LAYOUT.to_string(),
+            rename_files: false,
+            rename_template: DEFAULT_RENAME_TEMPLATE.to_string(),
+            scan_threads: 0,
             state: ProcessState::Idle,
             scan_receiver: None,
             organize_receiver: None,
-            found_files: Vec::new(),
+            cancel_token: CancelToken::default(),
+            organizing_mode: TransferMode::Move,
+            undo_receiver: None,
             organization_result: None,
+            history: Vec::new(),
+            undo_result: None,
+            confirm_undo: None,
+            resume_offer: None,
             show_support_modal: false,
             status_message: "Ready".to_string(),
             progress: 0.0,
+            progress_info: None,
+            files_discovered: 0,
             error_message: None,
             show_warning_modal: false,
         }
@@ -86,14 +155,30 @@ impl PhotoOrganizerApp {
             is_welcome_screen: true, 
             source_dir: config.last_source_dir.map(PathBuf::from),
             target_dir: config.last_target_dir.map(PathBuf::from),
+            separate_undated: config.separate_undated,
+            separate_raw: config.separate_raw,
+            transfer_mode: config.transfer_mode,
+            set_mtime_to_date_taken: config.set_mtime_to_date_taken,
+            folder_layout: config.folder_layout.unwrap_or_else(|| DEFAULT_FOLDER_LAYOUT.to_string()),
+            rename_files: config.rename_files,
+            rename_template: config.rename_template.unwrap_or_else(|| DEFAULT_RENAME_TEMPLATE.to_string()),
+            scan_threads: config.scan_threads,
             state: ProcessState::Idle,
             scan_receiver: None,
             organize_receiver: None,
-            found_files: Vec::new(),
+            cancel_token: CancelToken::default(),
+            organizing_mode: TransferMode::Move,
+            undo_receiver: None,
             organization_result: None,
+            history: Vec::new(),
+            undo_result: None,
+            confirm_undo: None,
+            resume_offer: None,
             show_support_modal: false,
             status_message: "Ready".to_string(),
             progress: 0.0,
+            progress_info: None,
+            files_discovered: 0,
             error_message: None,
             show_warning_modal: false,
         };
@@ -102,10 +187,9 @@ impl PhotoOrganizerApp {
         
         // Smart Startup
         if let Some(target) = &app.target_dir {
+             app.is_welcome_screen = !target.exists();
              if target.exists() {
-                 app.is_welcome_screen = false;
-             } else {
-                 app.is_welcome_screen = true;
+                 app.resume_offer = interrupted_run(target);
              }
         } else {
              app.is_welcome_screen = true;
@@ -147,94 +231,277 @@ impl PhotoOrganizerApp {
             last_target_dir: self.target_dir.as_ref().map(|p| p.to_string_lossy().to_string()),
             window_width: None,
             window_height: None,
+            separate_undated: self.separate_undated,
+            separate_raw: self.separate_raw,
+            transfer_mode: self.transfer_mode,
+            set_mtime_to_date_taken: self.set_mtime_to_date_taken,
+            folder_layout: Some(self.folder_layout.clone()),
+            rename_files: self.rename_files,
+            rename_template: Some(self.rename_template.clone()),
+            scan_threads: self.scan_threads,
         };
         config.save();
     }
 
+    fn organize_settings(&self) -> anyhow::Result<OrganizeSettings> {
+        let target_dir = match &self.target_dir {
+            Some(dir) => dir.clone(),
+            None => anyhow::bail!("No target directory configured"),
+        };
+        Ok(OrganizeSettings {
+            target_dir,
+            dry_run: false,
+            separate_undated: self.separate_undated,
+            separate_raw: self.separate_raw,
+            transfer_mode: self.transfer_mode,
+            set_mtime_to_date_taken: self.set_mtime_to_date_taken,
+            folder_layout: FolderLayout::parse(&self.folder_layout).context("Invalid folder layout")?,
+            rename_template: if self.rename_files {
+                Some(RenameTemplate::parse(&self.rename_template).context("Invalid rename template")?)
+            } else {
+                None
+            },
+        })
+    }
+
     // --- Background Operations ---
 
 
     fn start_analysis(&mut self) {
         self.error_message = None;
-        self.found_files.clear();
         
         let source = self.source_dir.clone().unwrap();
         let target = self.target_dir.clone().unwrap();
+        let scan_threads = self.scan_threads;
         
         if let Err(e) = validate_directories(&source, &target) {
             self.error_message = Some(e.to_string());
             return;
         }
+        let settings = match self.organize_settings() {
+            Ok(settings) => settings,
+            Err(e) => {
+                self.error_message = Some(format!("{:#}", e));
+                return;
+            }
+        };
         
         let (tx, rx) = channel();
         self.scan_receiver = Some(rx);
         self.state = ProcessState::Analyzing;
         self.status_message = "Scanning files...".to_string();
+        self.progress = 0.0;
+        self.progress_info = None;
+        self.files_discovered = 0;
+        self.cancel_token = CancelToken::default();
+        let cancel = self.cancel_token.clone();
         
         thread::spawn(move || {
             if let Err(e) = create_target_structure(&target) {
                 let _ = tx.send(ScanUpdate::Error(format!("Target prep failed: {}", e)));
                 return;
             }
-            match scan_directory(&source) {
-                Ok(files) => { let _ = tx.send(ScanUpdate::Complete(files)); },
+            let progress = |update| { let _ = tx.send(ScanUpdate::Progress(update)); };
+            match scan_directory(&source, scan_threads, &progress, &cancel) {
+                Ok(files) => {
+                    let _ = tx.send(ScanUpdate::Planning);
+                    match plan_organization(files, &settings, &cancel) {
+                        Ok(plan) => { let _ = tx.send(ScanUpdate::Complete(plan)); },
+                        Err(_) if cancel.is_cancelled() => { let _ = tx.send(ScanUpdate::Cancelled); },
+                        Err(e) => { let _ = tx.send(ScanUpdate::Error(e.to_string())); }
+                    }
+                },
+                Err(_) if cancel.is_cancelled() => { let _ = tx.send(ScanUpdate::Cancelled); },
                 Err(e) => { let _ = tx.send(ScanUpdate::Error(e.to_string())); }
             }
         });
     }
 
     fn start_organizing(&mut self) {
+        let plan = match &self.state {
+            ProcessState::AnalyzeComplete(plan) => plan.clone(),
+            _ => return,
+        };
+        // The plan was made with the settings of the analysis; applying it
+        // after they changed would not do what the preview showed.
+        if self.organize_settings().ok().as_ref() != Some(&plan.settings) {
+            self.error_message = Some("Settings changed since the analysis. Please analyze the files again.".to_string());
+            return;
+        }
+
         let (tx, rx) = channel();
         self.organize_receiver = Some(rx);
         self.state = ProcessState::Organizing;
+        self.organizing_mode = plan.settings.transfer_mode;
         self.status_message = "Organizing...".to_string();
         self.progress = 0.0;
-        
-        let files = self.found_files.clone();
-        let settings = OrganizeSettings {
-            target_dir: self.target_dir.clone().unwrap(),
-            dry_run: false,
-        };
+        self.progress_info = None;
+        self.cancel_token = CancelToken::default();
+        let cancel = self.cancel_token.clone();
         
         thread::spawn(move || {
-            let res = organize_files(files, &settings);
+            let progress = |info| { let _ = tx.send(OrganizeUpdate::Progress(info)); };
+            let res = execute_plan(plan, &progress, &cancel);
             let _ = tx.send(OrganizeUpdate::Complete(res));
         });
     }
     
+    fn start_resume(&mut self, run: &RunSummary) {
+        let (tx, rx) = channel();
+        self.scan_receiver = None;
+        self.organize_receiver = Some(rx);
+        self.current_tab = AppTab::Organize;
+        self.organization_result = None;
+        self.state = ProcessState::Organizing;
+        self.organizing_mode = run.mode;
+        self.status_message = "Resuming...".to_string();
+        self.progress = 0.0;
+        self.progress_info = None;
+        self.cancel_token = CancelToken::default();
+        let cancel = self.cancel_token.clone();
+
+        let journal_path = run.journal_path.clone();
+        thread::spawn(move || {
+            let progress = |info| { let _ = tx.send(OrganizeUpdate::Progress(info)); };
+            match resume_run(&journal_path, &progress, &cancel) {
+                Ok(res) => { let _ = tx.send(OrganizeUpdate::Complete(res)); },
+                Err(e) => { let _ = tx.send(OrganizeUpdate::Error(format!("{:#}", e))); }
+            }
+        });
+    }
+
+    // Only one background job may touch the library at a time.
+    fn can_start_job(&self) -> bool {
+        matches!(self.state, ProcessState::Idle | ProcessState::AnalyzeComplete(_))
+            && self.scan_receiver.is_none()
+            && self.organize_receiver.is_none()
+            && self.undo_receiver.is_none()
+    }
+
+    fn refresh_history(&mut self) {
+        self.history = match &self.target_dir {
+            Some(target) => list_runs(target),
+            None => Vec::new(),
+        };
+    }
+
+    fn start_undo(&mut self, run: &RunSummary) {
+        let (tx, rx) = channel();
+        self.undo_receiver = Some(rx);
+        self.undo_result = None;
+
+        let journal_path = run.journal_path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(undo_run(&journal_path).map_err(|e| e.to_string()));
+        });
+    }
+
     fn poll_updates(&mut self) {
-        // Scan polling
-        if let Some(rx) = &self.scan_receiver {
-            if let Ok(update) = rx.try_recv() {
-                match update {
-                    ScanUpdate::Complete(files) => {
-                        self.found_files = files;
-                        self.state = ProcessState::AnalyzeComplete(self.found_files.clone());
-                        self.scan_receiver = None; 
-                    },
-                    ScanUpdate::Error(e) => {
-                        self.error_message = Some(e);
-                        self.state = ProcessState::Idle;
-                        self.scan_receiver = None;
-                    }
+        // Scan polling. Progress arrives once per file, so drain everything
+        // that queued up since the last frame.
+        let scan_updates: Vec<ScanUpdate> = self.scan_receiver.iter().flat_map(|rx| rx.try_iter()).collect();
+        for update in scan_updates {
+            match update {
+                ScanUpdate::Progress(ScanProgress::Discovered(count)) => {
+                    self.files_discovered = count;
+                },
+                ScanUpdate::Progress(ScanProgress::Processed(info)) => {
+                    self.progress = info.fraction();
+                    self.progress_info = Some(info);
+                },
+                ScanUpdate::Planning => {
+                    self.status_message = "Checking the library for duplicates...".to_string();
+                },
+                ScanUpdate::Complete(plan) => {
+                    self.state = ProcessState::AnalyzeComplete(plan);
+                    self.scan_receiver = None; 
+                },
+                ScanUpdate::Cancelled => {
+                    self.state = ProcessState::Idle;
+                    self.scan_receiver = None;
+                },
+                ScanUpdate::Error(e) => {
+                    self.error_message = Some(e);
+                    self.state = ProcessState::Idle;
+                    self.scan_receiver = None;
                 }
             }
         }
         
         // Organize polling
-        if let Some(rx) = &self.organize_receiver {
-             if let Ok(update) = rx.try_recv() {
-                 match update {
-                     OrganizeUpdate::Complete(res) => {
-                         self.organization_result = Some(res);
-                         self.state = ProcessState::Idle;
-                         self.organize_receiver = None;
-                     },
-                 }
-             }
+        let organize_updates: Vec<OrganizeUpdate> = self.organize_receiver.iter().flat_map(|rx| rx.try_iter()).collect();
+        for update in organize_updates {
+            match update {
+                OrganizeUpdate::Progress(info) => {
+                    self.progress = info.fraction();
+                    self.progress_info = Some(info);
+                },
+                OrganizeUpdate::Complete(res) => {
+                    self.organization_result = Some(res);
+                    self.state = ProcessState::Idle;
+                    self.organize_receiver = None;
+                },
+                OrganizeUpdate::Error(e) => {
+                    self.error_message = Some(e);
+                    self.state = ProcessState::Idle;
+                    self.organize_receiver = None;
+                },
+            }
+        }
+    }
+
+    fn poll_undo(&mut self) {
+        let result = match self.undo_receiver.as_ref().map(|rx| rx.try_recv()) {
+            Some(Ok(result)) => result,
+            _ => return,
+        };
+        self.undo_receiver = None;
+        match result {
+            Ok(result) => self.undo_result = Some(result),
+            Err(e) => self.error_message = Some(e),
+        }
+        self.refresh_history();
+    }
+
+    // Progress bar with counts, throughput, ETA and the file being processed.
+    fn render_progress(&self, ui: &mut egui::Ui) {
+        let info = match &self.progress_info {
+            Some(info) => info,
+            None => {
+                ui.spinner();
+                return;
+            }
+        };
+
+        ui.add(egui::ProgressBar::new(self.progress).show_percentage());
+
+        let mut details = vec![format!("{} of {} files", info.files_done, info.files_total)];
+        if info.bytes_total > 0 {
+            details.push(format!("{} of {}", format_bytes(info.bytes_done as f64), format_bytes(info.bytes_total as f64)));
+        }
+        match info.rate() {
+            Some(rate) if info.bytes_total > 0 => details.push(format!("{}/s", format_bytes(rate))),
+            Some(rate) => details.push(format!("{:.0} files/s", rate)),
+            None => {},
+        }
+        if let Some(eta) = info.eta() {
+            details.push(format!("about {} left", format_duration(eta)));
+        }
+        ui.label(details.join(" · "));
+
+        if let Some(current) = &info.current_file {
+            ui.small(current.file_name().unwrap_or_default().to_string_lossy());
         }
     }
 
+    fn render_cancel_button(&self, ui: &mut egui::Ui) {
+        ui.add_space(10.0);
+        if self.cancel_token.is_cancelled() {
+            ui.label("Cancelling after the current file...");
+        } else if ui.button("Cancel").clicked() {
+            self.cancel_token.cancel();
+        }
+    }
 
     // --- Views ---
 
@@ -255,6 +522,11 @@ impl PhotoOrganizerApp {
             self.current_tab = AppTab::Organize;
         }
         ui.add_space(10.0);
+        if tab_btn(ui, "🕘  History", AppTab::History, current == AppTab::History) {
+            self.current_tab = AppTab::History;
+            self.refresh_history();
+        }
+        ui.add_space(10.0);
         if tab_btn(ui, "⚙  Settings", AppTab::Settings, current == AppTab::Settings) {
             self.current_tab = AppTab::Settings;
         }
@@ -328,13 +600,36 @@ impl PhotoOrganizerApp {
             ProcessState::Analyzing => {
                  ui.vertical_centered(|ui| {
                     ui.add_space(50.0);
-                    ui.spinner();
                     ui.heading("Analyzing Source...");
-                    ui.label("This may take a moment for large collections.");
+                    ui.label(&self.status_message);
+                    if self.progress_info.is_none() {
+                        ui.label(format!("{} files found so far...", self.files_discovered));
+                    }
+                    self.render_progress(ui);
+                    self.render_cancel_button(ui);
                 });
             },
-            ProcessState::AnalyzeComplete(files) => {
-                 ui.heading(format!("Analysis Complete: {} files found", files.len()));
+            ProcessState::AnalyzeComplete(plan) => {
+                 ui.heading(format!("Analysis Complete: {} files found", plan.items.len()));
+                 let low_confidence = plan.items.iter()
+                     .filter(|item| item.file.date_source.confidence() == DateConfidence::Low)
+                     .count();
+                 if low_confidence > 0 {
+                     ui.colored_label(egui::Color32::from_rgb(200, 120, 0), format!("⚠ {} files have no metadata date and use the file modification time.", low_confidence));
+                 }
+                 let mismatched = plan.items.iter().filter(|item| item.file.format_mismatch.is_some()).count();
+                 if mismatched > 0 {
+                     ui.colored_label(egui::Color32::from_rgb(200, 120, 0), format!("⚠ {} files have an extension that does not match their content.", mismatched));
+                 }
+                 let count = |action: fn(&PlanAction) -> bool| plan.items.iter().filter(|item| action(&item.action)).count();
+                 ui.label(format!(
+                     "{}: {} · Duplicates: {} · Name conflicts: {} · Skipped: {}",
+                     plan.settings.transfer_mode.label(),
+                     count(|a| matches!(a, PlanAction::Move | PlanAction::Copy)),
+                     count(|a| matches!(a, PlanAction::Duplicate { .. })),
+                     count(|a| matches!(a, PlanAction::Conflict)),
+                     count(|a| matches!(a, PlanAction::Skip)),
+                 ));
                  ui.add_space(10.0);
                  
                  ui.horizontal(|ui| {
@@ -349,24 +644,52 @@ impl PhotoOrganizerApp {
                  ui.separator();
                  ui.label("Preview:");
                  egui::ScrollArea::vertical().show(ui, |ui| {
-                    for file in files.iter().take(50) {
-                         ui.label(format!("{:?}", file.path.file_name().unwrap()));
+                    for item in plan.items.iter().take(50) {
+                         let file = &item.file;
+                         let mut name = format!("{:?}", file.path.file_name().unwrap());
+                         if let Some(dest) = &item.dest {
+                             let relative = dest.strip_prefix(&plan.settings.target_dir).unwrap_or(dest);
+                             name = format!("{} → {}", name, relative.display());
+                         }
+                         name = format!("[{}] {}", item.action.label(), name);
+                         if file.date_source.confidence() == DateConfidence::Low {
+                             ui.label(format!("⚠ {} ({})", name, file.date_source.label()));
+                         } else {
+                             ui.label(name);
+                         }
+                         if let Some(note) = &file.format_mismatch {
+                             ui.colored_label(egui::Color32::from_rgb(200, 120, 0), format!("    ⚠ {}", note));
+                         }
+                         if let Some(reason) = &item.reason {
+                             ui.weak(format!("    {}", reason));
+                         }
                     }
                  });
             },
             ProcessState::Organizing => {
                  ui.vertical_centered(|ui| {
                     ui.add_space(50.0);
-                    ui.spinner();
                     ui.heading("Organizing Photos...");
-                    ui.label("Moving files to Target directory...");
+                    ui.label(match self.organizing_mode {
+                        TransferMode::Move => "Moving files to Target directory...",
+                        TransferMode::Copy | TransferMode::Reflink => "Copying files to Target directory...",
+                        TransferMode::HardLink => "Linking files into Target directory...",
+                    });
+                    self.render_progress(ui);
+                    self.render_cancel_button(ui);
                 });
             }
         }
     }
 
     fn render_organization_report(&mut self, ui: &mut egui::Ui, res: &OrganizationResult) {
-        ui.heading("✅ Organization Complete");
+        if res.cancelled {
+            ui.heading("⚠ Organization Cancelled");
+            ui.label(format!("Stopped after {} of {} files. The remaining files were not touched.",
+                res.processed_files, res.processed_files + res.not_processed.len()));
+        } else {
+            ui.heading("✅ Organization Complete");
+        }
         ui.add_space(10.0);
         
         ui.group(|ui| {
@@ -374,8 +697,12 @@ impl PhotoOrganizerApp {
             ui.vertical(|ui| {
                 ui.strong("Summary:");
                 ui.label(format!("• Total Processed: {}", res.processed_files));
-                ui.label(format!("• Files Moved: {}", res.photos_moved + res.videos_moved));
+                let verb = if res.transfer_mode.keeps_source() { "Copied" } else { "Moved" };
+                ui.label(format!("• Files {}: {}", verb, res.photos_moved + res.videos_moved));
                 ui.label(format!("• Duplicates Found: {}", res.duplicates_found));
+                if res.low_confidence_dates > 0 {
+                    ui.label(format!("• Dated by File Modification Time: {}", res.low_confidence_dates));
+                }
                 
                 ui.add_space(10.0);
                 ui.separator();
@@ -392,6 +719,48 @@ impl PhotoOrganizerApp {
                 for (year, count) in &res.videos_per_year {
                     ui.label(format!("  • {}: {}", year, count));
                 }
+
+                if !res.renamed_files.is_empty() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    egui::CollapsingHeader::new(format!("Renamed Files: {}", res.renamed_files.len()))
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                for (original, dest) in &res.renamed_files {
+                                    let original_name = original.file_name().unwrap_or_default().to_string_lossy();
+                                    ui.label(format!("{} → {}", original_name, dest.display()));
+                                }
+                            });
+                        });
+                }
+
+                if !res.not_processed.is_empty() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    egui::CollapsingHeader::new(format!("Not Processed: {}", res.not_processed.len()))
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                for path in &res.not_processed {
+                                    ui.label(path.display().to_string());
+                                }
+                            });
+                        });
+                }
+
+                if !res.duplicates.is_empty() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    egui::CollapsingHeader::new(format!("Duplicates: {}", res.duplicates.len()))
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                for (duplicate, original) in &res.duplicates {
+                                    let duplicate_name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+                                    let original = self.target_dir.as_deref().and_then(|t| original.strip_prefix(t).ok()).unwrap_or(original);
+                                    ui.label(format!("{} = {}", duplicate_name, original.display()));
+                                }
+                            });
+                        });
+                }
             });
         });
 
@@ -419,7 +788,7 @@ impl PhotoOrganizerApp {
                             .min_size(egui::vec2(180.0, 32.0));
                         if ui.add(paypal_btn).clicked() {
                             let url = "https://www.paypal.com/donate/?business=Q4JJUB58QT7SN&no_recurring=1&item_name=Donations+help+me+purchase+a+Code+Signing+Certificate+to+remove+the+Unknown+Publisher+warning+and+build+trust+for+all+users.&currency_code=EUR";
-                            let _ = std::process::Command::new("cmd").args(&["/C", "start", "", url]).spawn();
+                            let _ = std::process::Command::new("cmd").args(["/C", "start", "", url]).spawn();
                         }
                         
                         ui.add_space(10.0);
@@ -428,7 +797,7 @@ impl PhotoOrganizerApp {
                             .min_size(egui::vec2(180.0, 32.0));
                         if ui.add(ideal_btn).clicked() {
                             let url = "https://betaalverzoek.rabobank.nl/betaalverzoek/?id=MiDjVyNBSN-Qy288Zb0sJg";
-                            let _ = std::process::Command::new("cmd").args(&["/C", "start", "", url]).spawn();
+                            let _ = std::process::Command::new("cmd").args(["/C", "start", "", url]).spawn();
                         }
                     });
                     
@@ -445,6 +814,163 @@ impl PhotoOrganizerApp {
             });
     }
 
+    fn render_history(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.heading("Import History");
+        ui.separator();
+
+        if self.target_dir.is_none() {
+            ui.colored_label(egui::Color32::RED, "Please configure Target Directory in Settings first.");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Every import into this library is journaled and can be undone.");
+            if ui.button("Refresh").clicked() {
+                self.refresh_history();
+            }
+        });
+
+        if self.undo_receiver.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Undoing import...");
+            });
+        }
+
+        if let Some(res) = &self.undo_result {
+            ui.group(|ui| {
+                ui.set_width(ui.available_width());
+                ui.strong("Last Undo:");
+                ui.label(format!("• Moved back to the source: {}", res.restored));
+                ui.label(format!("• Copies and links removed: {}", res.removed));
+                for warning in &res.warnings {
+                    ui.colored_label(egui::Color32::from_rgb(200, 120, 0), format!("⚠ {}", warning));
+                }
+                for error in &res.errors {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                if !res.errors.is_empty() {
+                    ui.label("Not everything could be reverted; undo the import again to retry the files left.");
+                }
+            });
+        }
+
+        ui.add_space(10.0);
+        if self.history.is_empty() {
+            ui.label("No imports recorded yet.");
+            return;
+        }
+
+        let can_undo = self.can_start_job();
+        let runs = self.history.clone();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for run in &runs {
+                ui.group(|ui| {
+                    ui.set_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{} · {} · {} files · {}",
+                            run.started.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                            run.mode.label(),
+                            run.files_transferred,
+                            run.status.label(),
+                        ));
+                        if run.status != RunStatus::Undone
+                            && ui.add_enabled(can_undo, egui::Button::new("Undo")).clicked()
+                        {
+                            self.confirm_undo = Some(run.clone());
+                        }
+                        if run.can_resume()
+                            && ui.add_enabled(can_undo, egui::Button::new("Resume")).clicked()
+                        {
+                            self.start_resume(run);
+                        }
+                    });
+                });
+            }
+        });
+    }
+
+    fn render_undo_modal(&mut self, ctx: &egui::Context) {
+        let run = match self.confirm_undo.clone() {
+            Some(run) => run,
+            None => return,
+        };
+
+        egui::Window::new("Undo Import?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.set_width(450.0);
+                ui.vertical(|ui| {
+                    ui.label(format!("Import of {}:", run.started.with_timezone(&Local).format("%Y-%m-%d %H:%M")));
+                    ui.label("• Moved files go back to their original folders.");
+                    ui.label("• Copies and links are deleted from the library; files edited since the import or whose source is gone are kept.");
+                    ui.label("• Folders created by the import are removed if they are empty.");
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("Cancel").clicked() {
+                                self.confirm_undo = None;
+                            }
+
+                            ui.add_space(10.0);
+
+                            if ui.add_enabled(self.can_start_job(), egui::Button::new("Undo Import")).clicked() {
+                                self.confirm_undo = None;
+                                self.start_undo(&run);
+                            }
+                        });
+                    });
+                });
+            });
+    }
+
+    fn render_resume_modal(&mut self, ctx: &egui::Context) {
+        let run = match self.resume_offer.clone() {
+            Some(run) => run,
+            None => return,
+        };
+
+        egui::Window::new("Resume Interrupted Import?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.set_width(450.0);
+                ui.vertical(|ui| {
+                    ui.label(format!(
+                        "The import of {} stopped after {} files.",
+                        run.started.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        run.files_transferred,
+                    ));
+                    ui.label("• The file that was being transferred is checked; an incomplete copy is removed.");
+                    ui.label("• The remaining files are organized with the settings of that import, without a new scan.");
+                    ui.label("• You can also resume or undo it later from the History tab.");
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("Later").clicked() {
+                                self.resume_offer = None;
+                            }
+
+                            ui.add_space(10.0);
+
+                            if ui.add_enabled(self.can_start_job(), egui::Button::new("Resume Import")).clicked() {
+                                self.resume_offer = None;
+                                self.start_resume(&run);
+                            }
+                        });
+                    });
+                });
+            });
+    }
+
     fn render_warning_modal(&mut self, ctx: &egui::Context) {
         egui::Window::new("Proceed with Organization?")
             .collapsible(false)
@@ -460,6 +986,12 @@ impl PhotoOrganizerApp {
                             ui.label("⚠️ Important Safety Checklist:");
                             ui.add_space(5.0);
                             ui.label("• Backup: Please ensure you have a backup before starting.");
+                            ui.label(match self.transfer_mode {
+                                TransferMode::Move => "• Transfer: Files will be moved out of the source folder.",
+                                TransferMode::Copy => "• Transfer: Files will be copied; the source folder is left untouched.",
+                                TransferMode::HardLink => "• Transfer: Files will be hard-linked (copied across drives); the source folder is left untouched. Editing a linked file changes both copies.",
+                                TransferMode::Reflink => "• Transfer: Files will be cloned where the drive supports it and copied otherwise; the source folder is left untouched.",
+                            });
                             ui.label("• Duplicates: These will be safely moved to a separate /Duplicates folder.");
                             ui.label("• Unsupported Files: Files like iPhone edit info (.AAE) will remain in the source folder.");
                         });
@@ -509,7 +1041,109 @@ impl PhotoOrganizerApp {
                     }
                 }
             });
-            ui.label("This is where your photos will be organized by Year.");
+            ui.label("This is where your photos will be organized, following the folder layout below.");
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            if ui.checkbox(&mut self.separate_undated, "Move files without a metadata date to an Undated folder").changed() {
+                self.save_config();
+            }
+            ui.label("Files dated only by their modification time are placed in /Undated for review instead of a year folder.");
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            if ui.checkbox(&mut self.separate_raw, "Keep RAW files in a separate RAW folder").changed() {
+                self.save_config();
+            }
+            ui.label("RAW camera files (CR2, CR3, NEF, ARW, DNG, RAF, ORF, RW2) use RAW instead of Photos as their category folder.");
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.label("Transfer Mode:");
+            ui.horizontal(|ui| {
+                for mode in TransferMode::ALL {
+                    if ui.radio_value(&mut self.transfer_mode, mode, mode.label()).changed() {
+                        self.save_config();
+                    }
+                }
+            });
+            ui.label(match self.transfer_mode {
+                TransferMode::Move => "Files are moved out of the source folder.",
+                TransferMode::Copy => "Files are copied and the source is never modified. Use this for SD cards and shared drives.",
+                TransferMode::HardLink => "Files are hard-linked into the library without using extra space. Only works on the same drive; other files are copied.",
+                TransferMode::Reflink => "Files are cloned (copy-on-write) on drives that support it, such as ReFS Dev Drives, and copied elsewhere.",
+            });
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            if ui.checkbox(&mut self.set_mtime_to_date_taken, "Set the file modification time to the capture date").changed() {
+                self.save_config();
+            }
+            ui.label("Makes file managers sort by capture date. Otherwise copies keep the original file times. Not applied to hard links, which share their times with the source.");
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.label("Folder Layout:");
+            ui.horizontal(|ui| {
+                if ui.text_edit_singleline(&mut self.folder_layout).changed() {
+                    self.save_config();
+                }
+                if ui.button("Reset").clicked() {
+                    self.folder_layout = DEFAULT_FOLDER_LAYOUT.to_string();
+                    self.save_config();
+                }
+            });
+            match FolderLayout::parse(&self.folder_layout) {
+                Ok(layout) => ui.monospace(format!("Example: {}", layout.preview().join("IMG_0001.jpg").display())),
+                Err(e) => ui.colored_label(egui::Color32::RED, e.to_string()),
+            };
+            ui.label("Placeholders: {category} (Photos, Videos or RAW), {year}, {month}, {day} and {camera_model}. Add :02 to zero-pad a number, e.g. {year}/{year}-{month:02}.");
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            if ui.checkbox(&mut self.rename_files, "Rename files on import").changed() {
+                self.save_config();
+            }
+            ui.add_enabled_ui(self.rename_files, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.text_edit_singleline(&mut self.rename_template).changed() {
+                        self.save_config();
+                    }
+                    if ui.button("Reset").clicked() {
+                        self.rename_template = DEFAULT_RENAME_TEMPLATE.to_string();
+                        self.save_config();
+                    }
+                });
+                match RenameTemplate::parse(&self.rename_template) {
+                    Ok(template) => ui.monospace(format!("Example: IMG_0001.JPG → {}", template.preview())),
+                    Err(e) => ui.colored_label(egui::Color32::RED, e.to_string()),
+                };
+                ui.label("Placeholders: {date:%Y%m%d_%H%M%S}, {camera}, {name} (original name), {seq} (numbers files that would get the same name, in capture order) and {ext}. Original names are listed in the report.");
+            });
+        });
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.label("Scan Threads:");
+            let slider = egui::Slider::new(&mut self.scan_threads, 0..=16)
+                .custom_formatter(|n, _| if n == 0.0 { "Automatic".to_string() } else { format!("{}", n) });
+            if ui.add(slider).changed() {
+                self.save_config();
+            }
+            ui.label("Number of files read at the same time during analysis. Automatic uses one per CPU core, which suits SSDs; use 1 or 2 for spinning disks.");
         });
     }
 
@@ -539,6 +1173,10 @@ impl App for PhotoOrganizerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
         // Poll for updates in thread channels
         self.poll_updates();
+        self.poll_undo();
+        if self.scan_receiver.is_some() || self.organize_receiver.is_some() || self.undo_receiver.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
         
         if self.is_welcome_screen {
             self.render_welcome(ctx);
@@ -556,6 +1194,7 @@ impl App for PhotoOrganizerApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.current_tab {
                 AppTab::Organize => self.render_organize(ctx, ui),
+                AppTab::History => self.render_history(ctx, ui),
                 AppTab::Settings => self.render_settings(ctx, ui),
             }
         });
@@ -580,6 +1219,14 @@ impl App for PhotoOrganizerApp {
         if self.show_warning_modal {
             self.render_warning_modal(ctx);
         }
+
+        if self.confirm_undo.is_some() {
+            self.render_undo_modal(ctx);
+        }
+
+        if self.resume_offer.is_some() {
+            self.render_resume_modal(ctx);
+        }
     }
 
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {